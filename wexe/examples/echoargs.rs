@@ -0,0 +1,63 @@
+//! Test helper for the integration tests: reports how it was launched as JSON on
+//! standard output (its arguments, working directory and environment), then sleeps for
+//! the milliseconds in the `ECHOARGS_SLEEP_MS` environment variable (default 0) and exits
+//! with the code in the `ECHOARGS_EXIT_CODE` environment variable (default 0).
+//! Arguments and variables that are not valid Unicode are reported lossily, and also
+//! as their raw bytes (on Unix) in `raw_args` and `raw_env`.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsString;
+
+use serde_json::{Value, json};
+
+/// The raw bytes of a value (on Unix; elsewhere the value itself, lossily)
+fn raw_bytes(value: &OsString) -> Value {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        json!(value.as_bytes())
+    }
+    #[cfg(not(unix))]
+    {
+        json!(value.to_string_lossy())
+    }
+}
+
+fn main() {
+    let args: Vec<OsString> = env::args_os().skip(1).collect();
+    let cwd = env::current_dir()
+        .map(|cwd| cwd.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let vars: BTreeMap<OsString, OsString> = env::vars_os().collect();
+    let raw_args: Vec<Value> = args.iter().map(raw_bytes).collect();
+    let raw_env: BTreeMap<String, Value> = vars
+        .iter()
+        .filter(|(k, v)| k.to_str().is_none() || v.to_str().is_none())
+        .map(|(k, v)| (k.to_string_lossy().to_string(), raw_bytes(v)))
+        .collect();
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string_lossy().to_string()).collect();
+    let env: BTreeMap<String, String> = vars
+        .iter()
+        .map(|(k, v)| (k.to_string_lossy().to_string(), v.to_string_lossy().to_string()))
+        .collect();
+    let report = json!({
+        "args": args,
+        "raw_args": raw_args,
+        "cwd": cwd,
+        "env": env,
+        "raw_env": raw_env,
+    });
+    println!("{report}");
+    if let Some(sleep_ms) = env::var("ECHOARGS_SLEEP_MS")
+        .ok()
+        .and_then(|ms| ms.parse::<u64>().ok())
+    {
+        std::thread::sleep(std::time::Duration::from_millis(sleep_ms));
+    }
+    let code = env::var("ECHOARGS_EXIT_CODE")
+        .ok()
+        .and_then(|code| code.parse::<i32>().ok())
+        .unwrap_or(0);
+    std::process::exit(code);
+}
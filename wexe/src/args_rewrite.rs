@@ -0,0 +1,215 @@
+//! Rewriting the arguments given to a wexe application before they are passed to the
+//! target, as configured by its `[[args.rewrite]]` rules and `[args.paths]` section.
+//! Arguments that are not valid Unicode are passed on as they are: the rules and
+//! patterns never match them.
+
+use std::ffi::OsString;
+use std::path::{self, Path};
+
+use regex::Regex;
+
+use crate::config_model::{ConfigArgPaths, ConfigPathMapping, ConfigRewriteRule};
+
+/// A compiled `[[args.rewrite]]` rule
+#[derive(Debug)]
+pub enum RewriteRule {
+    /// Replace the matches of `pattern` in each argument by `replacement`
+    Replace { pattern: Regex, replacement: String },
+    /// Remove the arguments matching the pattern
+    Drop(Regex),
+    /// Insert `argument` before the other arguments, unless one of them matches `unless`
+    Insert { argument: String, unless: Regex },
+}
+
+impl RewriteRule {
+    /// Compile a rule from the configuration file.
+    /// # Returns
+    /// The compiled rule, or a message describing why the rule is invalid.
+    pub fn compile(rule: &ConfigRewriteRule) -> Result<RewriteRule, String> {
+        let regex = |pattern: &str| Regex::new(pattern).map_err(|e| e.to_string());
+        let drop = rule.drop.unwrap_or(false);
+        match (&rule.pattern, &rule.replace, drop, &rule.insert) {
+            (Some(pattern), Some(replacement), false, None) => Ok(RewriteRule::Replace {
+                pattern: regex(pattern)?,
+                replacement: replacement.clone(),
+            }),
+            (Some(pattern), None, true, None) => Ok(RewriteRule::Drop(regex(pattern)?)),
+            (None, None, false, Some(argument)) => {
+                let unless = match &rule.unless {
+                    Some(unless) => regex(unless)?,
+                    None => regex(&format!("^{}$", regex::escape(argument)))?,
+                };
+                Ok(RewriteRule::Insert {
+                    argument: argument.clone(),
+                    unless,
+                })
+            }
+            _ => Err(
+                "expecting either 'match' and 'replace', 'match' and 'drop = true', or 'insert' \
+                (with an optional 'unless')"
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// Apply the rule to a list of arguments.
+    /// # Returns
+    /// The rewritten arguments, and a description of each change made.
+    pub fn apply(&self, args: Vec<OsString>) -> (Vec<OsString>, Vec<String>) {
+        let mut trace = Vec::new();
+        let args = match self {
+            RewriteRule::Replace {
+                pattern,
+                replacement,
+            } => args
+                .into_iter()
+                .map(|arg| {
+                    let Some(text) = arg.to_str() else {
+                        return arg;
+                    };
+                    let new = pattern.replace_all(text, replacement.as_str()).to_string();
+                    if new != text {
+                        trace.push(format!("replaced {text:?} by {new:?}"));
+                    }
+                    new.into()
+                })
+                .collect(),
+            RewriteRule::Drop(pattern) => args
+                .into_iter()
+                .filter(|arg| {
+                    let drop = arg.to_str().is_some_and(|text| pattern.is_match(text));
+                    if drop {
+                        trace.push(format!("dropped {arg:?}"));
+                    }
+                    !drop
+                })
+                .collect(),
+            RewriteRule::Insert { argument, unless } => {
+                if args.iter().filter_map(|arg| arg.to_str()).any(|arg| unless.is_match(arg)) {
+                    args
+                } else {
+                    trace.push(format!("inserted {argument:?}"));
+                    let mut new_args = vec![OsString::from(argument)];
+                    new_args.extend(args);
+                    new_args
+                }
+            }
+        };
+        (args, trace)
+    }
+}
+
+/// A list of `[[args.rewrite]]` rules, compiled once and applied in order.
+#[derive(Debug)]
+pub struct ArgRewriter {
+    /// The compiled rules, or for invalid rules the message describing why
+    rules: Vec<Result<RewriteRule, String>>,
+}
+
+impl ArgRewriter {
+    /// Compile a list of rewrite rules. Invalid rules are kept and skipped when the rules
+    /// are applied (rules from configuration files have been validated when loading them).
+    pub fn new(rules: &[ConfigRewriteRule]) -> ArgRewriter {
+        ArgRewriter {
+            rules: rules.iter().map(RewriteRule::compile).collect(),
+        }
+    }
+
+    /// Apply the rules, in order, to a list of arguments.
+    /// # Returns
+    /// The rewritten arguments, and a description of each change made, prefixed by the
+    /// (1-based) number of the rule that made it.
+    pub fn apply(&self, args: Vec<OsString>) -> (Vec<OsString>, Vec<String>) {
+        let mut args = args;
+        let mut trace = Vec::new();
+        for (index, rule) in self.rules.iter().enumerate() {
+            match rule {
+                Ok(rule) => {
+                    let (new_args, changes) = rule.apply(args);
+                    args = new_args;
+                    trace.extend(changes.into_iter().map(|c| format!("rule {}: {c}", index + 1)));
+                }
+                Err(e) => trace.push(format!("rule {}: skipped, invalid: {e}", index + 1)),
+            }
+        }
+        (args, trace)
+    }
+}
+
+/// Apply a prefix mapping to a path.
+/// # Returns
+/// The mapped path, or None if the path does not start with the prefix of the mapping.
+fn map_path(mapping: &ConfigPathMapping, path: &str) -> Option<OsString> {
+    let rest = path.strip_prefix(mapping.from.as_str())?;
+    let rest = match &mapping.separator {
+        Some(separator) => rest.replace(['/', '\\'], separator),
+        None => rest.to_string(),
+    };
+    Some(format!("{}{rest}", mapping.to).into())
+}
+
+/// The translation of path arguments configured by an `[args.paths]` section, with its
+/// `match` patterns compiled.
+#[derive(Debug)]
+pub struct PathTranslator {
+    patterns: Vec<Regex>,
+    existing: bool,
+    absolute: bool,
+    map: Vec<ConfigPathMapping>,
+}
+
+impl PathTranslator {
+    /// Compile an `[args.paths]` section. Invalid `match` patterns are skipped (patterns
+    /// from configuration files have been validated when loading them).
+    pub fn new(paths: &ConfigArgPaths) -> PathTranslator {
+        PathTranslator {
+            patterns: paths
+                .patterns
+                .iter()
+                .flatten()
+                .filter_map(|pattern| Regex::new(pattern).ok())
+                .collect(),
+            existing: paths.existing.unwrap_or(false),
+            absolute: paths.absolute.unwrap_or(true),
+            map: paths.map.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Translate the arguments that are paths: those matching one of the `match`
+    /// patterns, and (with `existing = true`) those naming an existing file or folder.
+    /// Relative paths are resolved against `cwd`.
+    /// # Returns
+    /// The translated arguments, and a description of each translation made.
+    pub fn translate(&self, args: Vec<OsString>, cwd: &Path) -> (Vec<OsString>, Vec<String>) {
+        let mut trace = Vec::new();
+        let args = args
+            .into_iter()
+            .map(|arg| {
+                let matches = arg
+                    .to_str()
+                    .is_some_and(|text| self.patterns.iter().any(|p| p.is_match(text)));
+                let is_path =
+                    !arg.is_empty() && (matches || (self.existing && cwd.join(&arg).exists()));
+                if !is_path {
+                    return arg;
+                }
+                let mut new = arg.clone();
+                if self.absolute && let Ok(full) = path::absolute(cwd.join(&arg)) {
+                    new = full.into_os_string();
+                }
+                // Prefix mappings are text substitutions, so they only apply to Unicode paths
+                let mapped = new
+                    .to_str()
+                    .and_then(|text| self.map.iter().find_map(|m| map_path(m, text)));
+                if let Some(mapped) = mapped {
+                    new = mapped;
+                }
+                if new != arg {
+                    trace.push(format!("path {arg:?} translated to {new:?}"));
+                }
+                new
+            })
+            .collect();
+        (args, trace)
+    }
+}
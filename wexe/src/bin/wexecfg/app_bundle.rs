@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use toml_edit::DocumentMut;
+
+use wexe::config_model::{
+    ConfigPathlike, ListOps, PathlikeOptions, WexeApp, WexeAppConfig, app_to_document, set_os_section,
+    set_profile_section, set_retry_section, update_document,
+};
+
+/// A portable collection of application configurations, as written by
+/// `/export` and read by `/import`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppBundle {
+    /// The application configurations, indexed by tag
+    pub apps: BTreeMap<String, WexeAppConfig>,
+}
+
+impl AppBundle {
+    pub fn new() -> AppBundle {
+        AppBundle {
+            apps: BTreeMap::new(),
+        }
+    }
+
+    /// Read a bundle from a TOML file.
+    pub fn load(file: &Path) -> Result<AppBundle, Box<dyn Error>> {
+        let text = fs::read_to_string(file)?;
+        let bundle: AppBundle = toml::from_str(&text)?;
+        Ok(bundle)
+    }
+
+    /// Serialize the bundle as a TOML document.
+    pub fn to_toml(&self) -> Result<String, Box<dyn Error>> {
+        Ok(toml::to_string(self)?)
+    }
+}
+
+/// Serialize a single application configuration as the text of a configuration file.
+pub fn app_config_to_toml(app: &WexeAppConfig) -> String {
+    let mut doc = app_to_document(&WexeApp::from_config(app.clone()));
+    set_sections(&mut doc, app);
+    doc.to_string()
+}
+
+/// Write the `[os.*]`, `[profiles.*]` and `[retry]` sections, the default profile, the
+/// launch flag prefix, the launch log and the timeout keys of `app` into a configuration
+/// document, replacing existing sections with the same name.
+fn set_sections(doc: &mut DocumentMut, app: &WexeAppConfig) {
+    if let Some(prefix) = &app.wexe_flag_prefix {
+        doc["wexe_flag_prefix"] = toml_edit::value(prefix.as_str());
+    }
+    if let Some(log) = &app.log {
+        doc["log"] = toml_edit::value(log.as_str());
+    }
+    if let Some(timeout) = &app.timeout {
+        doc["timeout"] = toml_edit::value(timeout.as_str());
+    }
+    if let Some(kill_after) = &app.kill_after {
+        doc["kill_after"] = toml_edit::value(kill_after.as_str());
+    }
+    if let Some(retry) = &app.retry {
+        set_retry_section(doc, retry);
+    }
+    if let Some(default_profile) = &app.default_profile {
+        doc["default_profile"] = toml_edit::value(default_profile.as_str());
+    }
+    for (name, section) in app.os.iter().flatten() {
+        set_os_section(doc, name, section);
+    }
+    for (name, profile) in app.profiles.iter().flatten() {
+        set_profile_section(doc, name, profile);
+    }
+}
+
+/// Replace the lists in `target` by those mentioned (`Some`).
+fn overlay_list_ops(target: &mut ListOps, prepend: Option<&Vec<String>>, append: Option<&Vec<String>>) {
+    if let Some(prepend) = prepend {
+        target.prepend = prepend.clone();
+    }
+    if let Some(append) = append {
+        target.append = append.clone();
+    }
+}
+
+/// Replace the PATH-like variable options in `target` by those `source` mentions.
+fn overlay_pathlike_options(target: &mut PathlikeOptions, source: &ConfigPathlike) {
+    let flags = [
+        (&mut target.dedupe, source.dedupe),
+        (&mut target.drop_missing, source.drop_missing),
+        (&mut target.normalize, source.normalize),
+    ];
+    for (flag, value) in flags {
+        if let Some(value) = value {
+            *flag = value;
+        }
+    }
+    if let Some(remove) = &source.remove {
+        target.remove = remove.clone();
+    }
+}
+
+/// Merge an application configuration into the text of an existing configuration
+/// file, returning the new text. Values in `app` replace the existing ones; everything
+/// `app` does not mention (including comments) is preserved.
+pub fn merge_app_config(existing_text: &str, app: &WexeAppConfig) -> Result<String, Box<dyn Error>> {
+    let mut doc = existing_text.parse::<DocumentMut>()?;
+    let existing: WexeAppConfig = toml::from_str(existing_text)?;
+    let mut merged = WexeApp::from_config(existing);
+    merged.target = app.target.clone();
+    if let Some(args) = &app.args {
+        overlay_list_ops(&mut merged.args, args.prepend.as_ref(), args.append.as_ref());
+        if let Some(rules) = &args.rewrite {
+            merged.args_rewrite = rules.clone();
+        }
+        if let Some(paths) = &args.paths {
+            merged.args_paths = Some(paths.clone());
+        }
+    }
+    if let Some(env) = &app.env {
+        for (name, value) in env.set.iter().flatten() {
+            merged.env_set.insert(name.clone(), value.clone());
+        }
+        for (name, ops) in env.pathlike.iter().flatten() {
+            let list_ops = merged.env_pathlike.entry(name.clone()).or_default();
+            overlay_list_ops(list_ops, ops.prepend.as_ref(), ops.append.as_ref());
+            let options = merged.env_pathlike_options.entry(name.clone()).or_default();
+            overlay_pathlike_options(options, ops);
+        }
+        merged.env_pathlike_options.retain(|_, options| *options != PathlikeOptions::default());
+        if let Some(clear) = env.clear {
+            merged.env_clear = clear;
+        }
+        if let Some(keep) = &env.keep {
+            merged.env_keep = keep.clone();
+        }
+    }
+    update_document(&mut doc, &merged);
+    set_sections(&mut doc, app);
+    Ok(doc.to_string())
+}
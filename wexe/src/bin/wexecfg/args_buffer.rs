@@ -57,6 +57,20 @@ impl ArgumentsBuffer {
         self.current_arg += count;
     }
 
+    /// Consume and return arguments up to the next one that looks like an option
+    /// (starts with '-') or the end of the buffer.
+    pub fn take_values(&mut self) -> Vec<String> {
+        let mut values = Vec::new();
+        while let Some(value) = self.peek() {
+            if value.starts_with('-') {
+                break;
+            }
+            values.push(value.to_string());
+            self.current_arg += 1;
+        }
+        values
+    }
+
     /// Return the number of arguments remaining in the buffer.
     pub fn remaining(&self) -> usize {
         self.all_args.len() - self.current_arg
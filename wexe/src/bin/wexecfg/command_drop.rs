@@ -26,19 +26,15 @@ impl DropCommandOptions {
 
     pub fn parse_args(&mut self, args: &mut ArgumentsBuffer) -> bool {
         while !args.is_empty() {
-            let arg_key = args.get();
-            match arg_key {
-                tag => {
-                    if !is_valid_app_tag(&tag) {
-                        eprintln!(
-                            "{fg_o}Expecting valid application tags as arguments: {fg_y}{tag}{fg_o} is not a valid tag{rst}."
-                        );
-                        return false;
-                    }
-                    self.targets.push(tag.to_string());
-                    args.skip(1);
-                }
+            let tag = args.get();
+            if !is_valid_app_tag(tag) {
+                eprintln!(
+                    "{fg_o}Expecting valid application tags as arguments: {fg_y}{tag}{fg_o} is not a valid tag{rst}."
+                );
+                return false;
             }
+            self.targets.push(tag.to_string());
+            args.skip(1);
         }
         if self.targets.is_empty() {
             eprintln!("{fg_o}Expecting at least one application tag as argument{rst}.");
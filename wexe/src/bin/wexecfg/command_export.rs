@@ -0,0 +1,117 @@
+use std::error::Error;
+use std::process::ExitCode;
+
+use wexe::config_model::{is_valid_app_tag, read_app_config};
+use wexe::console_colors::*;
+
+use super::app_bundle::AppBundle;
+use super::args_buffer::ArgumentsBuffer;
+use super::commands::{Command, CommandCollection};
+use super::wexe_repository::WexeRepository;
+
+pub struct ExportCommand {
+    names: Vec<&'static str>,
+}
+
+pub struct ExportCommandOptions {
+    /// The tags to export. Empty to export all applications.
+    pub tags: Vec<String>,
+}
+
+impl ExportCommandOptions {
+    pub fn new() -> ExportCommandOptions {
+        ExportCommandOptions { tags: Vec::new() }
+    }
+
+    pub fn parse_args(&mut self, args: &mut ArgumentsBuffer) -> bool {
+        while !args.is_empty() {
+            let arg_key = args.get().to_string();
+            match arg_key.as_str() {
+                "-tags" | "--tags" => {
+                    args.skip(1);
+                    if !parse_tag_list(&arg_key, args, &mut self.tags) {
+                        return false;
+                    }
+                }
+                _ => {
+                    eprintln!("{fg_o}Unrecognized option: {fg_y}{:}{rst}.", arg_key);
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Parse the list of application tags following a '--tags' option (`arg_key`) into `tags`.
+pub fn parse_tag_list(arg_key: &str, args: &mut ArgumentsBuffer, tags: &mut Vec<String>) -> bool {
+    let values = args.take_values();
+    if values.is_empty() {
+        eprintln!("{fg_o}Option {fg_y}{arg_key}{fg_o} requires at least one application tag{rst}.");
+        return false;
+    }
+    for tag in values {
+        if !is_valid_app_tag(&tag) {
+            eprintln!("{fg_o}Not a valid application tag: {fg_y}{tag}{rst}.");
+            return false;
+        }
+        tags.push(tag);
+    }
+    true
+}
+
+impl ExportCommand {
+    pub fn new() -> ExportCommand {
+        ExportCommand {
+            names: vec!["/export"],
+        }
+    }
+}
+
+impl Command for ExportCommand {
+    fn name(&self) -> &str {
+        self.names[0]
+    }
+
+    fn name_and_aliases(&self) -> &[&str] {
+        self.names.as_ref()
+    }
+
+    fn execute(
+        &self,
+        args: &mut ArgumentsBuffer,
+        commands: &CommandCollection,
+    ) -> Result<ExitCode, Box<dyn Error>> {
+        let mut options = ExportCommandOptions::new();
+        if !options.parse_args(args) {
+            commands.print_help_for(self.name());
+            return Ok(ExitCode::FAILURE);
+        }
+        let repo = WexeRepository::new();
+        for tag in options.tags.iter() {
+            if repo.find_entry(tag).is_none() {
+                eprintln!("{fg_r}No such application: {fg_y}{tag}{rst}.");
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        let mut bundle = AppBundle::new();
+        for entry in repo.get_entries() {
+            let tag = entry.get_tag();
+            if !options.tags.is_empty() && !options.tags.iter().any(|t| t == tag) {
+                continue;
+            }
+            match read_app_config(entry.get_cfg_path()) {
+                Ok(app) => {
+                    bundle.apps.insert(tag.to_string(), app);
+                }
+                Err(e) => {
+                    eprintln!("{fg_k}{tag:>20}{fg_W} : {fg_o}Not exported: {fg_r}{e}{rst}.");
+                }
+            }
+        }
+        // The bundle goes to stdout, everything else goes to stderr
+        print!("{}", bundle.to_toml()?);
+        eprintln!("Exported {fg_g}{}{rst} application(s).", bundle.apps.len());
+        Ok(ExitCode::SUCCESS)
+    }
+}
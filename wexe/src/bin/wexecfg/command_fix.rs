@@ -8,7 +8,7 @@ use wexe::console_colors::*;
 
 use super::args_buffer::ArgumentsBuffer;
 use super::commands::{Command, CommandCollection};
-use super::wexe_repository::{WexeRepository, target_missing_or_older};
+use super::wexe_repository::WexeRepository;
 
 pub struct FixCommand {
     names: Vec<&'static str>,
@@ -140,18 +140,29 @@ fn fix_tag(repo: &WexeRepository, tag: &str) -> Result<(), Box<dyn Error>> {
                 return Err("WEXE executable not installed.".into());
             }
             let stub_path = entry.get_stub_exe_path();
-            let fix_needed = target_missing_or_older(&wexe_path, &stub_path);
-            if fix_needed {
-                if stub_path.exists() {
-                    println!("{fg_c}{tag:>20}{fg_W} : {fg_b}Updating existing stub{rst}.");
-                    fs::copy(&wexe_path, &stub_path)?;
-                } else {
-                    println!("{fg_c}{tag:>20}{fg_W} : {fg_y}Creating missing stub{rst}.");
-                    fs::copy(&wexe_path, &stub_path)?;
+            let desired_kind = repo.get_desired_stub_kind(stub_path);
+            let strategy = desired_kind.name();
+            if repo.is_stub_current(stub_path) {
+                println!(
+                    "{fg_g}{tag:>20}{fg_W} : {fg_G}Stub is already up to date {fg_k}({strategy}){rst}."
+                );
+                return Ok(());
+            }
+            match repo.get_stub_kind(stub_path) {
+                Some(kind) if kind != desired_kind => {
+                    println!(
+                        "{fg_c}{tag:>20}{fg_W} : {fg_b}Converting stub from {fg_y}{}{fg_b} to {fg_y}{strategy}{rst}.",
+                        kind.name()
+                    );
+                }
+                Some(_) => {
+                    println!("{fg_c}{tag:>20}{fg_W} : {fg_b}Updating existing stub {fg_k}({strategy}){rst}.");
+                }
+                None => {
+                    println!("{fg_c}{tag:>20}{fg_W} : {fg_y}Creating missing stub {fg_k}({strategy}){rst}.");
                 }
-            } else {
-                println!("{fg_g}{tag:>20}{fg_W} : {fg_G}Stub is already up to date{rst}.");
             }
+            repo.create_stub(stub_path, desired_kind)?;
             Ok(())
         }
         None => {
@@ -168,7 +179,7 @@ fn fix_tag(repo: &WexeRepository, tag: &str) -> Result<(), Box<dyn Error>> {
     }
 }
 
-fn fix_tags(tags: &Vec<String>) -> Result<(), Box<dyn Error>> {
+pub fn fix_tags(tags: &Vec<String>) -> Result<(), Box<dyn Error>> {
     let repo: WexeRepository = WexeRepository::new();
     for tag in tags {
         fix_tag(&repo, tag)?;
@@ -182,36 +193,16 @@ fn fix_all() -> Result<(), Box<dyn Error>> {
     for entry in repo.get_entries() {
         tags_set.insert(entry.get_tag().to_string());
     }
-    for direntry in fs::read_dir(repo.get_config_folder()).unwrap() {
-        let entry = direntry.unwrap();
-        let path = entry.path();
-        if path.is_file() {
-            let is_exe: bool;
-            match path.extension() {
-                Some(ext) => {
-                    if ext.to_ascii_lowercase() == std::env::consts::EXE_EXTENSION {
-                        is_exe = true;
-                    } else {
-                        is_exe = false;
-                    }
-                }
-                None => {
-                    is_exe = std::env::consts::EXE_EXTENSION == "";
-                }
-            }
-            if is_exe {
-                let tag = path.file_stem().unwrap().to_string_lossy().to_string();
-                if tag != tag.to_lowercase() {
-                    eprintln!(
-                        "{fg_r}Skipping executable containing upper case characters in its name: {fg_y}{}{rst} ({fg_m}manual fix required{rst}).",
-                        path.to_string_lossy()
-                    );
-                    continue;
-                }
-                if is_valid_app_tag(&tag) {
-                    tags_set.insert(tag.to_string());
-                }
-            }
+    for (tag, path) in repo.find_stub_files() {
+        if tag != tag.to_lowercase() {
+            eprintln!(
+                "{fg_r}Skipping executable containing upper case characters in its name: {fg_y}{}{rst} ({fg_m}manual fix required{rst}).",
+                path.to_string_lossy()
+            );
+            continue;
+        }
+        if is_valid_app_tag(&tag) {
+            tags_set.insert(tag);
         }
     }
     let tags = tags_set.into_iter().collect();
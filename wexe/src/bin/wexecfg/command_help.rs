@@ -9,7 +9,7 @@ pub struct HelpCommand {
 }
 
 impl HelpCommand {
-    pub fn new<'a>() -> HelpCommand {
+    pub fn new() -> HelpCommand {
         HelpCommand {
             names: vec!["/help", "/h"],
         }
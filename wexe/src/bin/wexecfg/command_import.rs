@@ -0,0 +1,270 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use wexe::config_model::{CONFIG_SCHEMA, is_valid_app_tag};
+use wexe::console_colors::*;
+
+use super::app_bundle::{AppBundle, app_config_to_toml, merge_app_config};
+use super::args_buffer::ArgumentsBuffer;
+use super::command_fix::fix_tags;
+use super::commands::{Command, CommandCollection};
+use super::path_rebase::PathRebase;
+use super::wexe_repository::WexeRepository;
+
+pub struct ImportCommand {
+    names: Vec<&'static str>,
+}
+
+/// What to do with applications in the bundle that already exist
+#[derive(Clone, Copy, PartialEq)]
+pub enum ImportConflictPolicy {
+    /// Merge the imported configuration into the existing one (imported values win)
+    Merge,
+    /// Replace the existing configuration (after backing it up)
+    Overwrite,
+    /// Keep the existing configuration
+    SkipExisting,
+}
+
+pub struct ImportCommandOptions {
+    pub bundle_file: Option<PathBuf>,
+    pub policy: Option<ImportConflictPolicy>,
+    pub dry_run: bool,
+    /// Path prefix to replace in the imported configurations
+    pub from: Option<String>,
+    /// Replacement for the `from` path prefix
+    pub to: Option<String>,
+}
+
+impl ImportCommandOptions {
+    pub fn new() -> ImportCommandOptions {
+        ImportCommandOptions {
+            bundle_file: None,
+            policy: None,
+            dry_run: false,
+            from: None,
+            to: None,
+        }
+    }
+
+    fn set_policy(&mut self, policy: ImportConflictPolicy, arg_key: &str) -> bool {
+        if self.policy.is_some_and(|p| p != policy) {
+            eprintln!(
+                "{fg_o}Only one of {fg_y}--merge{fg_o}, {fg_y}--overwrite{fg_o} and \
+                {fg_y}--skip-existing{fg_o} can be given; unexpected {fg_y}{arg_key}{rst}."
+            );
+            return false;
+        }
+        self.policy = Some(policy);
+        true
+    }
+
+    pub fn parse_args(&mut self, args: &mut ArgumentsBuffer) -> bool {
+        while !args.is_empty() {
+            let arg_key = args.get();
+            match arg_key {
+                "-merge" | "--merge" => {
+                    if !self.set_policy(ImportConflictPolicy::Merge, arg_key) {
+                        return false;
+                    }
+                    args.skip(1);
+                }
+                "-overwrite" | "--overwrite" => {
+                    if !self.set_policy(ImportConflictPolicy::Overwrite, arg_key) {
+                        return false;
+                    }
+                    args.skip(1);
+                }
+                "-skip-existing" | "--skip-existing" => {
+                    if !self.set_policy(ImportConflictPolicy::SkipExisting, arg_key) {
+                        return false;
+                    }
+                    args.skip(1);
+                }
+                "-dry-run" | "--dry-run" => {
+                    self.dry_run = true;
+                    args.skip(1);
+                }
+                "-from" | "--from" | "-to" | "--to" => {
+                    if args.remaining() < 2 {
+                        eprintln!(
+                            "{fg_o}Option {fg_y}{arg_key}{fg_o} requires an argument {fg_W}(a path prefix).{rst}",
+                        );
+                        return false;
+                    }
+                    let prefix = Some(args.get_at(1).to_string());
+                    if arg_key.ends_with("from") {
+                        self.from = prefix;
+                    } else {
+                        self.to = prefix;
+                    }
+                    args.skip(2);
+                }
+                file => {
+                    if file.starts_with('-') {
+                        eprintln!("{fg_o}Unrecognized option: {fg_y}{file}{rst}.");
+                        return false;
+                    }
+                    if self.bundle_file.is_some() {
+                        eprintln!("{fg_o}Only one bundle file can be imported at a time{rst}.");
+                        return false;
+                    }
+                    let path = PathBuf::from(file);
+                    if !path.is_file() {
+                        eprintln!("{fg_o}Bundle file {fg_y}{file}{fg_o} does not exist{rst}.");
+                        return false;
+                    }
+                    self.bundle_file = Some(path);
+                    args.skip(1);
+                }
+            }
+        }
+        if self.bundle_file.is_none() {
+            eprintln!("{fg_o}No bundle file specified{rst}.");
+            return false;
+        }
+        if self.from.is_some() != self.to.is_some() {
+            eprintln!("{fg_o}Options {fg_y}--from{fg_o} and {fg_y}--to{fg_o} must be used together{rst}.");
+            return false;
+        }
+        true
+    }
+}
+
+impl ImportCommand {
+    pub fn new() -> ImportCommand {
+        ImportCommand {
+            names: vec!["/import"],
+        }
+    }
+}
+
+/// Write the configuration text for `tag`, backing up any existing configuration file.
+fn save_config(cfg_path: &Path, text: &str) -> Result<(), Box<dyn Error>> {
+    if cfg_path.exists() {
+        fs::rename(cfg_path, cfg_path.with_extension("toml.bak"))?;
+    }
+    fs::write(cfg_path, text)?;
+    Ok(())
+}
+
+impl Command for ImportCommand {
+    fn name(&self) -> &str {
+        self.names[0]
+    }
+
+    fn name_and_aliases(&self) -> &[&str] {
+        self.names.as_ref()
+    }
+
+    fn execute(
+        &self,
+        args: &mut ArgumentsBuffer,
+        commands: &CommandCollection,
+    ) -> Result<ExitCode, Box<dyn Error>> {
+        let mut options = ImportCommandOptions::new();
+        if !options.parse_args(args) {
+            commands.print_help_for(self.name());
+            return Ok(ExitCode::FAILURE);
+        }
+        let mut bundle = AppBundle::load(options.bundle_file.as_ref().unwrap())?;
+        if let (Some(from), Some(to)) = (&options.from, &options.to) {
+            let rebase = match PathRebase::new(from, to) {
+                Ok(rebase) => rebase,
+                Err(message) => {
+                    eprintln!("{fg_r}{message}{rst}.");
+                    return Ok(ExitCode::FAILURE);
+                }
+            };
+            for (tag, app) in bundle.apps.iter_mut() {
+                let count = rebase.rebase_app_config(app).len();
+                if count > 0 {
+                    println!("{fg_c}{tag:>20}{fg_W} : {fg_y}Rebased {count} path(s){rst}.");
+                }
+            }
+        }
+        let repo = WexeRepository::new();
+
+        // Pre-flight: report what will happen to each application before touching anything
+        let title_tag = "Application";
+        let title_action = "Action";
+        let title_target = "Target";
+        println!("{stl_u}{title_tag:<20} | {stl_u}{title_action:<16} | {stl_u}{title_target:<40}{rst}");
+        let mut invalid_tags = 0;
+        let mut unsupported = 0;
+        let mut conflicts = 0;
+        for (tag, app) in bundle.apps.iter() {
+            let (action_style, action) = if !is_valid_app_tag(tag) || tag == "wexe" || tag == "wexecfg"
+            {
+                invalid_tags += 1;
+                (fg_r, "Invalid tag")
+            } else if app.schema.unwrap_or(0) > CONFIG_SCHEMA {
+                unsupported += 1;
+                (fg_r, "Newer schema")
+            } else if repo.get_config_path(tag).exists() {
+                conflicts += 1;
+                match options.policy {
+                    Some(ImportConflictPolicy::Merge) => (fg_y, "Merge"),
+                    Some(ImportConflictPolicy::Overwrite) => (fg_y, "Overwrite"),
+                    Some(ImportConflictPolicy::SkipExisting) => (fg_k, "Skip (exists)"),
+                    None => (fg_r, "Conflict"),
+                }
+            } else {
+                (fg_g, "Create")
+            };
+            let target_text = if Path::new(&app.target).exists() {
+                format!("{fg_c}{}{rst}", app.target)
+            } else {
+                format!("{fg_r}{stl_i}* Target missing on this machine: {rst}{fg_o}{}{rst}", app.target)
+            };
+            println!("{action_style}{tag:<20}{rst} | {action_style}{action:<16}{rst} | {target_text}");
+        }
+        if invalid_tags > 0 {
+            eprintln!("{fg_r}The bundle contains invalid application tags; nothing imported{rst}.");
+            return Ok(ExitCode::FAILURE);
+        }
+        if unsupported > 0 {
+            eprintln!(
+                "{fg_r}The bundle contains configurations for a newer configuration schema than \
+                {fg_o}{CONFIG_SCHEMA}{fg_r}; nothing imported. Upgrade wexe first{rst}."
+            );
+            return Ok(ExitCode::FAILURE);
+        }
+        if conflicts > 0 && options.policy.is_none() {
+            eprintln!(
+                "{fg_o}{conflicts} application(s) already exist. Use {fg_y}--merge{fg_o}, \
+                {fg_y}--overwrite{fg_o} or {fg_y}--skip-existing{fg_o} to decide what to do{rst}."
+            );
+            return Ok(ExitCode::FAILURE);
+        }
+        if options.dry_run {
+            println!("{fg_y}Dry run{rst}: nothing imported.");
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        let mut imported: Vec<String> = Vec::new();
+        for (tag, app) in bundle.apps.iter() {
+            let cfg_path = repo.get_config_path(tag);
+            if cfg_path.exists() {
+                match options.policy {
+                    Some(ImportConflictPolicy::Merge) => {
+                        let existing_text = fs::read_to_string(&cfg_path)?;
+                        save_config(&cfg_path, &merge_app_config(&existing_text, app)?)?;
+                    }
+                    Some(ImportConflictPolicy::Overwrite) => {
+                        save_config(&cfg_path, &app_config_to_toml(app))?;
+                    }
+                    _ => continue,
+                }
+            } else {
+                save_config(&cfg_path, &app_config_to_toml(app))?;
+            }
+            imported.push(tag.clone());
+        }
+        println!("Imported {fg_g}{}{rst} application(s). Updating stubs:", imported.len());
+        fix_tags(&imported)?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
@@ -79,7 +79,7 @@ impl Command for InstallCommand {
         let repo = WexeRepository::new();
         let cfg_folder = repo.get_config_folder();
 
-        if is_same_file(&current_exe_folder, &cfg_folder).unwrap() {
+        if is_same_file(current_exe_folder, cfg_folder).unwrap() {
             println!(
                 "{fg_r}Error!{fg_o} The current exe folder is the same as the config folder{rst}. \
                 \n{fg_y}Run {fg_o}wexecfg /install{fg_y} from the directory where it was built{rst}."
@@ -89,7 +89,7 @@ impl Command for InstallCommand {
 
         let wexecfg_dest = repo.get_wexecfg_exe_path();
         let wexecfg_short_name = &exe.file_name().unwrap();
-        if target_missing_or_older(&exe, &wexecfg_dest) {
+        if target_missing_or_older(&exe, wexecfg_dest) {
             println!(
                 "Copying {fg_b}{:}{fg_W} to {fg_g}{:}{rst}.",
                 &exe.to_string_lossy(),
@@ -111,7 +111,7 @@ impl Command for InstallCommand {
                 wexe_source.set_file_name(wexe_short_name);
                 wexe_source
             };
-            if target_missing_or_older(&wexe_source, &wexe_dest) {
+            if target_missing_or_older(&wexe_source, wexe_dest) {
                 println!(
                     "Copying {fg_b}{:}{fg_W} to {fg_g}{:}{rst}.",
                     &wexe_source.to_string_lossy(),
@@ -125,7 +125,7 @@ impl Command for InstallCommand {
                 );
             }
         } else {
-            if target_missing_or_older(&exe, &wexe_dest) {
+            if target_missing_or_older(&exe, wexe_dest) {
                 println!(
                     "{fg_o}{}{fg_y} not copied. Use {fg_g}-wexe{fg_y} to include it{rst}.",
                     wexe_short_name.to_string_lossy()
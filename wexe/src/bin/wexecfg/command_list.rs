@@ -1,12 +1,17 @@
 use std::error::Error;
 use std::process::ExitCode;
 
+use chrono::Utc;
+
 use super::args_buffer::ArgumentsBuffer;
+use super::command_stats::{DEFAULT_UNUSED_DAYS, last_used_column, parse_unused_days};
 use super::commands::{Command, CommandCollection};
 use super::wexe_repository::WexeRepository;
 use super::wexe_repository::get_file_stamp;
 
+use wexe::config_model::ConfigError;
 use wexe::console_colors::*;
+use wexe::usage_stats::{UsageStats, get_wexe_stats_file, read_usage_stats};
 
 pub struct ListCommand {
     names: Vec<&'static str>,
@@ -14,6 +19,9 @@ pub struct ListCommand {
 
 struct ListCommandOptions {
     pub filter: Option<String>,
+    /// Whether to include the usage statistics
+    pub stats: bool,
+    pub unused_days: i64,
 }
 
 impl ListCommand {
@@ -26,7 +34,11 @@ impl ListCommand {
 
 impl ListCommandOptions {
     pub fn new() -> ListCommandOptions {
-        ListCommandOptions { filter: None }
+        ListCommandOptions {
+            filter: None,
+            stats: false,
+            unused_days: DEFAULT_UNUSED_DAYS,
+        }
     }
 
     pub fn parse_args(&mut self, args: &mut ArgumentsBuffer) -> bool {
@@ -41,6 +53,14 @@ impl ListCommandOptions {
                     self.filter = Some(args.get_at(1).to_string());
                     args.skip(2);
                 }
+                "--stats" => {
+                    self.stats = true;
+                    args.skip(1);
+                }
+                "--unused" => match parse_unused_days(args) {
+                    Some(days) => self.unused_days = days,
+                    None => return false,
+                },
                 _ => {
                     eprintln!("{fg_o}Unrecognized option: {fg_y}{:}{rst}.", arg_key);
                     return false;
@@ -70,54 +90,97 @@ impl Command for ListCommand {
             commands.print_help_for(self.name());
             return Ok(ExitCode::FAILURE);
         }
-        println!("Registered applications{rst}:");
         let repo = WexeRepository::new();
+        println!(
+            "Registered applications {fg_k}(stub strategy: {fg_y}{}{fg_k}){rst}:",
+            repo.get_stub_strategy().name()
+        );
         let apps = repo.get_entries();
         let title_tag = "Application";
         let title_stub = "Stub status";
+        let title_kind = "Kind";
         let title_target = "Target";
+        let stats: Option<UsageStats> = if options.stats {
+            Some(read_usage_stats(&get_wexe_stats_file())?)
+        } else {
+            None
+        };
+        let title_stats = if stats.is_some() {
+            format!("{stl_u}{:>8} | {stl_u}{:<16} | ", "Launches", "Last used")
+        } else {
+            String::new()
+        };
+        let now = Utc::now();
         println!(
-            "{stl_u}{title_tag:<20} | {stl_u}{title_stub:<16} | {stl_u}{title_target:<40}.{rst}"
+            "{stl_u}{title_tag:<20} | {stl_u}{title_stub:<16} | {stl_u}{title_kind:<9} | {title_stats}{stl_u}{title_target:<40}.{rst}"
         );
         for app in apps.iter() {
             let tag = app.get_tag();
-            if let Some(filter) = &options.filter {
-                if !tag.contains(filter) {
-                    continue;
-                }
+            if let Some(filter) = &options.filter
+                && !tag.contains(filter)
+            {
+                continue;
             }
             let target_exe_path = app.get_target_exe_path();
-            let target_text = match target_exe_path {
-                Some(path) => path.to_str().unwrap(),
-                None => app.get_load_error().as_ref().unwrap(),
+            let target_text = match (target_exe_path, app.get_load_error()) {
+                (Some(path), _) => path.to_string_lossy().to_string(),
+                (None, Some(ConfigError::NonAbsoluteTarget { target, .. })) => target.clone(),
+                (None, Some(ConfigError::Parse { message, line_col: Some((line, column)), .. })) => {
+                    format!("line {line}, column {column}: {message}")
+                }
+                (None, Some(e)) => e.to_string(),
+                (None, None) => String::new(),
             };
             let style_tag;
             let style_target;
             if target_exe_path.is_none() {
                 style_tag = format!("{stl_i}{fg_r}");
-                style_target = format!("{stl_i}{fg_r}* Configuration Load Error: ");
+                style_target = match app.get_load_error() {
+                    Some(ConfigError::NonAbsoluteTarget { .. }) => {
+                        format!("{stl_i}{fg_r}* Target path is not absolute: {rst}{fg_o}")
+                    }
+                    Some(ConfigError::Parse { .. }) => {
+                        format!("{stl_i}{fg_r}* Configuration Parse Error, {rst}{fg_o}")
+                    }
+                    _ => format!("{stl_i}{fg_r}* Configuration Load Error: "),
+                };
             } else if !target_exe_path.as_ref().unwrap().exists() {
                 style_tag = format!("{stl_i}{fg_r}");
                 style_target = format!("{fg_r}{stl_i}* Target file missing: {rst}{stl_s}{fg_o}");
             } else {
-                style_tag = format!("{fg_g}");
-                style_target = format!("");
+                style_tag = fg_g.to_string();
+                style_target = String::new();
             }
             let stub_stamp = get_file_stamp(app.get_stub_exe_path());
             let stub_style: String;
             let stub_stamp_text: String = match stub_stamp {
                 Some(stamp) => {
-                    let stamp_text = stamp.format("%Y%m%d-%H%M%S").to_string();
-                    stub_style = format!("{fg_y}");
-                    format!("{:}", stamp_text)
+                    stub_style = fg_y.to_string();
+                    stamp.format("%Y%m%d-%H%M%S").to_string()
                 }
                 None => {
                     stub_style = format!("{fg_o}{stl_i}");
-                    format!("Stub missing",)
+                    "Stub missing".to_string()
+                }
+            };
+            let stub_path = app.get_stub_exe_path();
+            let (kind_style, kind_text) = match repo.get_stub_kind(stub_path) {
+                Some(kind) if repo.is_stub_current(stub_path) => (fg_y, kind.name()),
+                Some(kind) => (fg_o, kind.name()),
+                None => (fg_k, "-"),
+            };
+            let stats_text = match &stats {
+                Some(stats) => {
+                    let app_stats = stats.get(tag);
+                    let launches = app_stats.map(|s| s.launches).unwrap_or(0);
+                    let (last_used_style, last_used) =
+                        last_used_column(app_stats, now, options.unused_days);
+                    format!("{fg_y}{launches:>8}{rst} | {last_used_style}{last_used:<16}{rst} | ")
                 }
+                None => String::new(),
             };
             println!(
-                "{style_tag}{tag:<20}{rst} | {stub_style}{stub_stamp_text:<16}{rst} | {style_target}{target_text}{rst}"
+                "{style_tag}{tag:<20}{rst} | {stub_style}{stub_stamp_text:<16}{rst} | {kind_style}{kind_text:<9}{rst} | {stats_text}{style_target}{target_text}{rst}"
             );
         }
         Ok(ExitCode::SUCCESS)
@@ -0,0 +1,155 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+
+use toml_edit::DocumentMut;
+
+use wexe::config_model::{CONFIG_SCHEMA, is_valid_app_tag, migrate_document};
+use wexe::console_colors::*;
+
+use super::args_buffer::ArgumentsBuffer;
+use super::commands::{Command, CommandCollection};
+use super::wexe_repository::WexeRepository;
+
+pub struct MigrateCommand {
+    names: Vec<&'static str>,
+}
+
+pub struct MigrateCommandOptions {
+    pub all: bool,
+    pub tags: Vec<String>,
+    pub dry_run: bool,
+}
+
+impl MigrateCommandOptions {
+    pub fn new() -> MigrateCommandOptions {
+        MigrateCommandOptions {
+            all: false,
+            tags: Vec::new(),
+            dry_run: false,
+        }
+    }
+
+    pub fn parse_args(&mut self, args: &mut ArgumentsBuffer) -> bool {
+        while !args.is_empty() {
+            let arg_key = args.get().to_string();
+            match arg_key.as_str() {
+                "-all" => {
+                    self.all = true;
+                    args.skip(1);
+                }
+                "-dry-run" | "--dry-run" => {
+                    self.dry_run = true;
+                    args.skip(1);
+                }
+                tag => {
+                    if !is_valid_app_tag(tag) {
+                        if tag.starts_with('-') {
+                            eprintln!("{fg_o}Unrecognized option: {fg_y}{tag}{rst}.");
+                        } else {
+                            eprintln!("{fg_o}Not a valid application tag: {fg_y}{tag}{rst}.");
+                        }
+                        return false;
+                    }
+                    self.tags.push(tag.to_string());
+                    args.skip(1);
+                }
+            }
+        }
+        if self.all && !self.tags.is_empty() {
+            eprintln!("{fg_o}Option {fg_y}-all{fg_o} cannot be combined with application tags{rst}.");
+            return false;
+        }
+        if !self.all && self.tags.is_empty() {
+            eprintln!("{fg_o}Expecting {fg_y}-all{fg_o} or one or more application tags{rst}.");
+            return false;
+        }
+        true
+    }
+}
+
+impl MigrateCommand {
+    pub fn new() -> MigrateCommand {
+        MigrateCommand {
+            names: vec!["/migrate"],
+        }
+    }
+}
+
+/// Upgrade the configuration file of one application to the current schema.
+/// Returns true if the file needed upgrading.
+fn migrate_app(tag: &str, cfg_path: &Path, dry_run: bool) -> Result<bool, Box<dyn Error>> {
+    let text = fs::read_to_string(cfg_path)?;
+    let mut doc = text.parse::<DocumentMut>()?;
+    let steps = migrate_document(cfg_path, &mut doc)?;
+    if steps.is_empty() {
+        println!("{fg_g}{tag:>20}{fg_W} : {fg_G}Already at schema {CONFIG_SCHEMA}{rst}.");
+        return Ok(false);
+    }
+    if dry_run {
+        println!("{fg_c}{tag:>20}{fg_W} : {fg_y}Would upgrade to schema {CONFIG_SCHEMA}{rst}:");
+    } else {
+        fs::copy(cfg_path, cfg_path.with_extension("toml.bak"))?;
+        fs::write(cfg_path, doc.to_string())?;
+        println!("{fg_c}{tag:>20}{fg_W} : {fg_b}Upgraded to schema {CONFIG_SCHEMA}{rst}:");
+    }
+    for step in steps {
+        println!("{:>20}   {fg_W}\u{2022} {step}{rst}", "");
+    }
+    Ok(true)
+}
+
+impl Command for MigrateCommand {
+    fn name(&self) -> &str {
+        self.names[0]
+    }
+
+    fn name_and_aliases(&self) -> &[&str] {
+        self.names.as_ref()
+    }
+
+    fn execute(
+        &self,
+        args: &mut ArgumentsBuffer,
+        commands: &CommandCollection,
+    ) -> Result<ExitCode, Box<dyn Error>> {
+        let mut options = MigrateCommandOptions::new();
+        if !options.parse_args(args) {
+            commands.print_help_for(self.name());
+            return Ok(ExitCode::FAILURE);
+        }
+        let repo = WexeRepository::new();
+        for tag in options.tags.iter() {
+            if repo.find_entry(tag).is_none() {
+                eprintln!("{fg_r}No such application: {fg_y}{tag}{rst}.");
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        let mut migrated = 0;
+        let mut failed = 0;
+        for entry in repo.get_entries() {
+            let tag = entry.get_tag();
+            if !options.all && !options.tags.iter().any(|t| t == tag) {
+                continue;
+            }
+            match migrate_app(tag, entry.get_cfg_path(), options.dry_run) {
+                Ok(true) => migrated += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    failed += 1;
+                    eprintln!("{fg_r}{tag:>20}{fg_W} : {fg_o}Not migrated: {fg_R}{e}{rst}.");
+                }
+            }
+        }
+        if options.dry_run {
+            println!("{fg_y}Dry run{rst}: {migrated} application(s) would be upgraded.");
+        } else {
+            println!("Upgraded {fg_g}{migrated}{rst} application(s).");
+        }
+        if failed > 0 {
+            return Ok(ExitCode::FAILURE);
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+}
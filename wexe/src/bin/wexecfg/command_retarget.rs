@@ -0,0 +1,204 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+
+use toml_edit::DocumentMut;
+
+use wexe::config_model::{
+    WexeApp, WexeAppConfig, resolve_app_config, set_os_section, set_profile_section,
+    update_document,
+};
+use wexe::console_colors::*;
+
+use super::args_buffer::ArgumentsBuffer;
+use super::command_export::parse_tag_list;
+use super::commands::{Command, CommandCollection};
+use super::path_rebase::PathRebase;
+use super::wexe_repository::WexeRepository;
+
+pub struct RetargetCommand {
+    names: Vec<&'static str>,
+}
+
+pub struct RetargetCommandOptions {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    /// The tags to retarget. Empty to retarget all applications.
+    pub tags: Vec<String>,
+    pub dry_run: bool,
+}
+
+impl RetargetCommandOptions {
+    pub fn new() -> RetargetCommandOptions {
+        RetargetCommandOptions {
+            from: None,
+            to: None,
+            tags: Vec::new(),
+            dry_run: false,
+        }
+    }
+
+    pub fn parse_args(&mut self, args: &mut ArgumentsBuffer) -> bool {
+        while !args.is_empty() {
+            let arg_key = args.get().to_string();
+            match arg_key.as_str() {
+                "-from" | "--from" | "-to" | "--to" => {
+                    if args.remaining() < 2 {
+                        eprintln!(
+                            "{fg_o}Option {fg_y}{arg_key}{fg_o} requires an argument {fg_W}(a path prefix).{rst}",
+                        );
+                        return false;
+                    }
+                    let prefix = Some(args.get_at(1).to_string());
+                    if arg_key.ends_with("from") {
+                        self.from = prefix;
+                    } else {
+                        self.to = prefix;
+                    }
+                    args.skip(2);
+                }
+                "-tags" | "--tags" => {
+                    args.skip(1);
+                    if !parse_tag_list(&arg_key, args, &mut self.tags) {
+                        return false;
+                    }
+                }
+                "-dry-run" | "--dry-run" => {
+                    self.dry_run = true;
+                    args.skip(1);
+                }
+                _ => {
+                    eprintln!("{fg_o}Unrecognized option: {fg_y}{:}{rst}.", arg_key);
+                    return false;
+                }
+            }
+        }
+        if self.from.is_none() || self.to.is_none() {
+            eprintln!("{fg_o}Both {fg_y}--from{fg_o} and {fg_y}--to{fg_o} are required{rst}.");
+            return false;
+        }
+        true
+    }
+}
+
+impl RetargetCommand {
+    pub fn new() -> RetargetCommand {
+        RetargetCommand {
+            names: vec!["/retarget"],
+        }
+    }
+}
+
+/// Rebase the configuration file of one application. Returns the number of changed values.
+fn retarget_app(
+    rebase: &PathRebase,
+    tag: &str,
+    cfg_path: &Path,
+    dry_run: bool,
+) -> Result<usize, Box<dyn Error>> {
+    let text = fs::read_to_string(cfg_path)?;
+    let mut doc = text.parse::<DocumentMut>()?;
+    let mut cfg: WexeAppConfig = toml::from_str(&text)?;
+    let changes = rebase.rebase_app_config(&mut cfg);
+    if changes.is_empty() {
+        return Ok(0);
+    }
+    update_document(&mut doc, &WexeApp::from_config(cfg.clone()));
+    for (name, section) in cfg.os.iter().flatten() {
+        let prefix = format!("os.{name}.");
+        if changes.iter().any(|change| change.key.starts_with(&prefix)) {
+            set_os_section(&mut doc, name, section);
+        }
+    }
+    for (name, profile) in cfg.profiles.iter().flatten() {
+        let prefix = format!("profiles.{name}.");
+        if changes.iter().any(|change| change.key.starts_with(&prefix)) {
+            set_profile_section(&mut doc, name, profile);
+        }
+    }
+    println!("{fg_c}{tag}{rst} ({fg_k}{}{rst}):", cfg_path.to_string_lossy());
+    for change in changes.iter() {
+        println!("  {fg_W}{}{rst}", change.key);
+        println!("    {fg_r}- {}{rst}", change.old);
+        println!("    {fg_g}+ {}{rst}", change.new);
+    }
+    if !dry_run {
+        fs::copy(cfg_path, cfg_path.with_extension("toml.bak"))?;
+        fs::write(cfg_path, doc.to_string())?;
+        // Validate the rebased model as it applies here (with the [os.*] section of
+        // this operating system and the selected profile)
+        match resolve_app_config(cfg_path.to_path_buf(), cfg, None) {
+            Ok(app) => {
+                if Path::new(&app.target).exists() {
+                    println!("  {fg_g}Updated; new target exists{rst}.");
+                } else {
+                    println!(
+                        "  {fg_r}Updated, but the new target does not exist: {fg_o}{}{rst}.",
+                        app.target
+                    );
+                }
+            }
+            Err(e) => {
+                println!("  {fg_r}Updated, but the configuration no longer loads: {fg_o}{e}{rst}.");
+            }
+        }
+    }
+    Ok(changes.len())
+}
+
+impl Command for RetargetCommand {
+    fn name(&self) -> &str {
+        self.names[0]
+    }
+
+    fn name_and_aliases(&self) -> &[&str] {
+        self.names.as_ref()
+    }
+
+    fn execute(
+        &self,
+        args: &mut ArgumentsBuffer,
+        commands: &CommandCollection,
+    ) -> Result<ExitCode, Box<dyn Error>> {
+        let mut options = RetargetCommandOptions::new();
+        if !options.parse_args(args) {
+            commands.print_help_for(self.name());
+            return Ok(ExitCode::FAILURE);
+        }
+        let rebase = match PathRebase::new(options.from.as_ref().unwrap(), options.to.as_ref().unwrap()) {
+            Ok(rebase) => rebase,
+            Err(message) => {
+                eprintln!("{fg_r}{message}{rst}.");
+                return Ok(ExitCode::FAILURE);
+            }
+        };
+        let repo = WexeRepository::new();
+        for tag in options.tags.iter() {
+            if repo.find_entry(tag).is_none() {
+                eprintln!("{fg_r}No such application: {fg_y}{tag}{rst}.");
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        let mut changed_apps = 0;
+        for entry in repo.get_entries() {
+            let tag = entry.get_tag();
+            if !options.tags.is_empty() && !options.tags.iter().any(|t| t == tag) {
+                continue;
+            }
+            match retarget_app(&rebase, tag, entry.get_cfg_path(), options.dry_run) {
+                Ok(0) => {}
+                Ok(_) => changed_apps += 1,
+                Err(e) => {
+                    eprintln!("{fg_r}{tag:>20}{fg_W} : {fg_o}Not retargeted: {fg_R}{e}{rst}.");
+                }
+            }
+        }
+        if options.dry_run {
+            println!("{fg_y}Dry run{rst}: {changed_apps} application(s) would be changed.");
+        } else {
+            println!("Changed {fg_g}{changed_apps}{rst} application(s).");
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+}
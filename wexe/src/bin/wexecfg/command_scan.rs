@@ -0,0 +1,301 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::env::consts::EXE_SUFFIX;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use same_file::is_same_file;
+use serde::Serialize;
+
+use wexe::console_colors::*;
+
+use super::args_buffer::ArgumentsBuffer;
+use super::commands::{Command, CommandCollection};
+use super::wexe_repository::{WexeRepository, is_executable_file};
+
+pub struct ScanCommand {
+    names: Vec<&'static str>,
+}
+
+pub struct ScanCommandOptions {
+    pub json: bool,
+    /// Folders with at most this many unwrapped executables are suggested as wrap candidates
+    pub max_unwrapped: usize,
+}
+
+/// An application whose name matches an executable in a folder on PATH
+#[derive(Serialize)]
+struct ShadowFinding {
+    tag: String,
+    path: String,
+}
+
+/// Analysis of a single folder on PATH (other than the wexe folder)
+#[derive(Serialize)]
+struct FolderFinding {
+    folder: String,
+    executables: usize,
+    /// Tags of applications targetting executables in this folder
+    wrapped: Vec<String>,
+    /// Executables in this folder that no application targets
+    unwrapped: Vec<String>,
+    /// True if this folder has wrapped executables and all its executables are
+    /// wrapped, so the folder can be removed from PATH
+    droppable: bool,
+}
+
+#[derive(Serialize)]
+struct ScanReport {
+    wexe_folder: String,
+    /// Position of the wexe folder in PATH, if it is on PATH at all
+    wexe_path_index: Option<usize>,
+    /// Applications that are hidden by an executable of the same name earlier on PATH
+    shadowed: Vec<ShadowFinding>,
+    /// Applications that hide an executable of the same name later on PATH
+    shadowing: Vec<ShadowFinding>,
+    folders: Vec<FolderFinding>,
+}
+
+impl ScanCommandOptions {
+    pub fn new() -> ScanCommandOptions {
+        ScanCommandOptions {
+            json: false,
+            max_unwrapped: 5,
+        }
+    }
+
+    pub fn parse_args(&mut self, args: &mut ArgumentsBuffer) -> bool {
+        while !args.is_empty() {
+            let arg_key = args.get();
+            match arg_key {
+                "-json" | "--json" => {
+                    self.json = true;
+                    args.skip(1);
+                }
+                "-max" => {
+                    if args.remaining() < 2 {
+                        eprintln!(
+                            "{fg_o}Option {fg_y}-max{fg_o} requires an argument {fg_W}(a number).{rst}",
+                        );
+                        return false;
+                    }
+                    match args.get_at(1).parse::<usize>() {
+                        Ok(max) => self.max_unwrapped = max,
+                        Err(_) => {
+                            eprintln!(
+                                "{fg_o}Expecting a number after {fg_y}-max{fg_o}, not {fg_y}{}{rst}.",
+                                args.get_at(1)
+                            );
+                            return false;
+                        }
+                    }
+                    args.skip(2);
+                }
+                _ => {
+                    eprintln!("{fg_o}Unrecognized option: {fg_y}{:}{rst}.", arg_key);
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+impl ScanCommand {
+    pub fn new() -> ScanCommand {
+        ScanCommand {
+            names: vec!["/scan"],
+        }
+    }
+}
+
+/// List the names of the executable files in a folder, sorted.
+fn list_executables(folder: &Path) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    if let Ok(read_dir) = fs::read_dir(folder) {
+        for direntry in read_dir.flatten() {
+            let path = direntry.path();
+            if is_executable_file(&path) {
+                names.push(direntry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+    names.sort();
+    names
+}
+
+fn scan_path(repo: &WexeRepository, options: &ScanCommandOptions) -> ScanReport {
+    let wexe_folder = repo.get_config_folder();
+    let path_folders: Vec<PathBuf> = match env::var_os("PATH") {
+        Some(path) => env::split_paths(&path)
+            .filter(|p| !p.as_os_str().is_empty() && p.is_dir())
+            .collect(),
+        None => Vec::new(),
+    };
+    let wexe_path_index = path_folders
+        .iter()
+        .position(|p| is_same_file(p, wexe_folder).unwrap_or(false));
+
+    // For each folder containing targets: the file names of those targets and
+    // the tags of the applications targetting them.
+    let mut targets_by_folder: BTreeMap<PathBuf, BTreeMap<String, String>> = BTreeMap::new();
+    for entry in repo.get_entries() {
+        if let Some(target) = entry.get_target_exe_path()
+            && let (Some(parent), Some(name)) = (target.parent(), target.file_name())
+        {
+            let parent = fs::canonicalize(parent).unwrap_or(parent.to_path_buf());
+            targets_by_folder.entry(parent).or_default().insert(
+                name.to_string_lossy().to_string(),
+                entry.get_tag().to_string(),
+            );
+        }
+    }
+
+    let mut shadowed: Vec<ShadowFinding> = Vec::new();
+    let mut shadowing: Vec<ShadowFinding> = Vec::new();
+    let mut folders: Vec<FolderFinding> = Vec::new();
+    let mut seen: BTreeSet<PathBuf> = BTreeSet::new();
+    for (index, folder) in path_folders.iter().enumerate() {
+        if Some(index) == wexe_path_index {
+            continue;
+        }
+        let canonical = fs::canonicalize(folder).unwrap_or(folder.clone());
+        if !seen.insert(canonical.clone()) {
+            // the same folder appears more than once on PATH
+            continue;
+        }
+        for entry in repo.get_entries() {
+            let candidate = folder.join(entry.get_tag().to_string() + EXE_SUFFIX);
+            // The application's own target later on PATH is expected: the stub runs it.
+            // Earlier on PATH it bypasses the stub, which is shadowing like any other.
+            let is_own_target = match entry.get_target_exe_path() {
+                Some(target) => is_same_file(&candidate, target).unwrap_or(false),
+                None => false,
+            };
+            let after_wexe = wexe_path_index.is_some_and(|wexe_index| wexe_index < index);
+            if !(is_own_target && after_wexe) && is_executable_file(&candidate) {
+                let finding = ShadowFinding {
+                    tag: entry.get_tag().to_string(),
+                    path: candidate.to_string_lossy().to_string(),
+                };
+                match wexe_path_index {
+                    Some(wexe_index) if wexe_index < index => shadowing.push(finding),
+                    _ => shadowed.push(finding),
+                }
+            }
+        }
+        let executables = list_executables(folder);
+        let targets = targets_by_folder.get(&canonical);
+        let mut wrapped: Vec<String> = Vec::new();
+        let mut unwrapped: Vec<String> = Vec::new();
+        for name in executables.iter() {
+            match targets.and_then(|t| t.get(name)) {
+                Some(tag) => wrapped.push(tag.clone()),
+                None => unwrapped.push(name.clone()),
+            }
+        }
+        if unwrapped.len() > options.max_unwrapped {
+            // Too much work to wrap everything in this folder; not worth suggesting
+            continue;
+        }
+        folders.push(FolderFinding {
+            folder: folder.to_string_lossy().to_string(),
+            executables: executables.len(),
+            droppable: !wrapped.is_empty() && unwrapped.is_empty(),
+            wrapped,
+            unwrapped,
+        });
+    }
+
+    ScanReport {
+        wexe_folder: wexe_folder.to_string_lossy().to_string(),
+        wexe_path_index,
+        shadowed,
+        shadowing,
+        folders,
+    }
+}
+
+fn print_report(report: &ScanReport, options: &ScanCommandOptions) {
+    match report.wexe_path_index {
+        Some(index) => println!(
+            "The wexe folder {fg_g}{}{rst} is entry {fg_y}{}{rst} on PATH.",
+            report.wexe_folder, index
+        ),
+        None => println!(
+            "{fg_r}The wexe folder {fg_y}{}{fg_r} is not on PATH{rst}.",
+            report.wexe_folder
+        ),
+    }
+
+    let title_tag = "Application";
+    let title_path = "Executable";
+    println!("\n{fg_W}Applications hidden by an executable earlier on PATH{rst}:");
+    println!("{stl_u}{title_tag:<20} | {stl_u}{title_path:<40}{rst}");
+    for finding in report.shadowed.iter() {
+        println!("{fg_r}{:<20}{rst} | {fg_o}{}{rst}", finding.tag, finding.path);
+    }
+    println!("\n{fg_W}Applications hiding an executable later on PATH{rst}:");
+    println!("{stl_u}{title_tag:<20} | {stl_u}{title_path:<40}{rst}");
+    for finding in report.shadowing.iter() {
+        println!("{fg_y}{:<20}{rst} | {fg_c}{}{rst}", finding.tag, finding.path);
+    }
+
+    let title_folder = "Folder on PATH";
+    let title_count = "Exes";
+    let title_advice = "Suggestion";
+    println!(
+        "\n{fg_W}Folders that could be dropped from PATH \
+        (at most {fg_y}{}{fg_W} executables left to wrap){rst}:",
+        options.max_unwrapped
+    );
+    println!("{stl_u}{title_folder:<40} | {stl_u}{title_count:>5} | {stl_u}{title_advice:<30}{rst}");
+    for finding in report.folders.iter() {
+        let advice = if finding.droppable {
+            format!("{fg_g}Drop from PATH (all wrapped){rst}")
+        } else if finding.unwrapped.is_empty() {
+            format!("{fg_o}No executables{rst}")
+        } else {
+            format!(
+                "{fg_y}Wrap {fg_c}{}{fg_y} to drop{rst}",
+                finding.unwrapped.join(", ")
+            )
+        };
+        println!(
+            "{fg_b}{:<40}{rst} | {:>5} | {advice}",
+            finding.folder, finding.executables
+        );
+    }
+}
+
+impl Command for ScanCommand {
+    fn name(&self) -> &str {
+        self.names[0]
+    }
+
+    fn name_and_aliases(&self) -> &[&str] {
+        self.names.as_ref()
+    }
+
+    fn execute(
+        &self,
+        args: &mut ArgumentsBuffer,
+        commands: &CommandCollection,
+    ) -> Result<ExitCode, Box<dyn Error>> {
+        let mut options = ScanCommandOptions::new();
+        if !options.parse_args(args) {
+            commands.print_help_for(self.name());
+            return Ok(ExitCode::FAILURE);
+        }
+        let repo = WexeRepository::new();
+        let report = scan_path(&repo, &options);
+        if options.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_report(&report, &options);
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+}
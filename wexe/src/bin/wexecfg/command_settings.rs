@@ -0,0 +1,141 @@
+use std::error::Error;
+use std::fs;
+use std::process::ExitCode;
+
+use toml_edit::DocumentMut;
+
+use wexe::config_model::{get_wexe_settings_file, read_wexe_settings};
+use wexe::console_colors::*;
+use wexe::launch_log::{DEFAULT_LOG_KEEP, DEFAULT_LOG_MAX_SIZE};
+
+use super::args_buffer::ArgumentsBuffer;
+use super::commands::{Command, CommandCollection};
+use super::wexe_repository::StubKind;
+
+pub struct SettingsCommand {
+    names: Vec<&'static str>,
+}
+
+struct SettingsCommandOptions {
+    /// The setting to change, and its new value. None to show all settings.
+    pub assignment: Option<(String, String)>,
+}
+
+impl SettingsCommandOptions {
+    pub fn new() -> SettingsCommandOptions {
+        SettingsCommandOptions { assignment: None }
+    }
+
+    pub fn parse_args(&mut self, args: &mut ArgumentsBuffer) -> bool {
+        if args.is_empty() {
+            return true;
+        }
+        if args.remaining() != 2 {
+            eprintln!("{fg_o}Expecting a setting name and its new value{rst}.");
+            return false;
+        }
+        let name = args.get().to_string();
+        let value = args.get_at(1).to_string();
+        args.skip(2);
+        match name.as_str() {
+            "stub_strategy" => {
+                if StubKind::parse(&value).is_none() {
+                    eprintln!(
+                        "{fg_o}Unknown stub strategy {fg_y}{value}{fg_o}; expecting \
+                        {fg_y}copy{fg_o}, {fg_y}hardlink{fg_o}, {fg_y}symlink{fg_o} or {fg_y}script{rst}."
+                    );
+                    return false;
+                }
+            }
+            "log" => {}
+            "stats" => {
+                if value.parse::<bool>().is_err() {
+                    eprintln!(
+                        "{fg_o}Expecting {fg_y}true{fg_o} or {fg_y}false{fg_o} for {fg_y}stats{fg_o}, not {fg_y}{value}{rst}."
+                    );
+                    return false;
+                }
+            }
+            "log_max_size" | "log_keep" => {
+                if value.parse::<u32>().is_err() {
+                    eprintln!("{fg_o}Expecting a number for {fg_y}{name}{fg_o}, not {fg_y}{value}{rst}.");
+                    return false;
+                }
+            }
+            _ => {
+                eprintln!("{fg_o}Unknown setting: {fg_y}{name}{rst}.");
+                return false;
+            }
+        }
+        self.assignment = Some((name, value));
+        true
+    }
+}
+
+impl SettingsCommand {
+    pub fn new() -> SettingsCommand {
+        SettingsCommand {
+            names: vec!["/settings"],
+        }
+    }
+}
+
+impl Command for SettingsCommand {
+    fn name(&self) -> &str {
+        self.names[0]
+    }
+
+    fn name_and_aliases(&self) -> &[&str] {
+        self.names.as_ref()
+    }
+
+    fn execute(
+        &self,
+        args: &mut ArgumentsBuffer,
+        commands: &CommandCollection,
+    ) -> Result<ExitCode, Box<dyn Error>> {
+        let mut options = SettingsCommandOptions::new();
+        if !options.parse_args(args) {
+            commands.print_help_for(self.name());
+            return Ok(ExitCode::FAILURE);
+        }
+        let settings_file = get_wexe_settings_file();
+        if let Some((name, value)) = options.assignment {
+            // Edit the document in place, so comments in the settings file are preserved
+            let text = if settings_file.exists() {
+                fs::read_to_string(&settings_file)?
+            } else {
+                String::new()
+            };
+            let mut doc = text.parse::<DocumentMut>()?;
+            doc[name.as_str()] = match (value.parse::<i64>(), value.parse::<bool>()) {
+                (_, Ok(flag)) if name == "stats" => toml_edit::value(flag),
+                (Ok(number), _) if name != "log" => toml_edit::value(number),
+                _ => toml_edit::value(value.as_str()),
+            };
+            fs::write(&settings_file, doc.to_string())?;
+            println!("{fg_W}{name}{rst} = {fg_y}{value}{rst}");
+            if name == "stub_strategy" {
+                println!(
+                    "Run {fg_y}wexecfg /fix -all{rst} to convert existing stubs to the new strategy."
+                );
+            }
+            return Ok(ExitCode::SUCCESS);
+        }
+        let settings = read_wexe_settings()?;
+        println!("Settings ({fg_k}{}{rst}):", settings_file.to_string_lossy());
+        let stub_strategy = settings.stub_strategy.as_deref().unwrap_or("copy");
+        println!("{fg_c}{:>20}{fg_W} : {fg_y}{stub_strategy}{rst}", "stub_strategy");
+        match &settings.log {
+            Some(log) => println!("{fg_c}{:>20}{fg_W} : {fg_y}{log}{rst}", "log"),
+            None => println!("{fg_c}{:>20}{fg_W} : {fg_k}(none){rst}", "log"),
+        }
+        let max_size = settings.log_max_size.unwrap_or(DEFAULT_LOG_MAX_SIZE);
+        println!("{fg_c}{:>20}{fg_W} : {fg_y}{max_size}{rst}", "log_max_size");
+        let keep = settings.log_keep.unwrap_or(DEFAULT_LOG_KEEP);
+        println!("{fg_c}{:>20}{fg_W} : {fg_y}{keep}{rst}", "log_keep");
+        let stats = settings.stats.unwrap_or(false);
+        println!("{fg_c}{:>20}{fg_W} : {fg_y}{stats}{rst}", "stats");
+        Ok(ExitCode::SUCCESS)
+    }
+}
@@ -0,0 +1,341 @@
+use std::env;
+use std::error::Error;
+use std::path::Path;
+use std::process::ExitCode;
+
+use wexe::config_model::{
+    WEXE_PROFILE_VAR, WexeApp, WexeAppConfig, is_valid_app_tag, read_app_config,
+    read_config_file_with_profile, read_wexe_settings,
+};
+use wexe::console_colors::*;
+use wexe::launch_log::LaunchLog;
+use wexe::launch_retry::{Backoff, RetryPolicy};
+use wexe::launch_timeout::{LaunchTimeout, TIMEOUT_EXIT_CODE, format_duration};
+use wexe::launcher::Launcher;
+
+use super::args_buffer::ArgumentsBuffer;
+use super::commands::{Command, CommandCollection};
+use super::wexe_repository::WexeRepository;
+
+pub struct ShowCommand {
+    names: Vec<&'static str>,
+}
+
+pub struct ShowCommandOptions {
+    pub tag: Option<String>,
+    pub profile: Option<String>,
+    pub effective: bool,
+}
+
+impl ShowCommandOptions {
+    pub fn new() -> ShowCommandOptions {
+        ShowCommandOptions {
+            tag: None,
+            profile: None,
+            effective: false,
+        }
+    }
+
+    pub fn parse_args(&mut self, args: &mut ArgumentsBuffer) -> bool {
+        while !args.is_empty() {
+            let arg_key = args.get();
+            if arg_key == "-profile" || arg_key == "--profile" {
+                if args.remaining() < 2 {
+                    eprintln!(
+                        "{fg_o}Option {fg_y}{arg_key}{fg_o} requires an argument {fg_W}(a profile name).{rst}",
+                    );
+                    return false;
+                }
+                self.profile = Some(args.get_at(1).to_string());
+                args.skip(2);
+                continue;
+            }
+            if arg_key == "-effective" || arg_key == "--effective" {
+                self.effective = true;
+                args.skip(1);
+                continue;
+            }
+            if arg_key.starts_with('-') {
+                eprintln!("{fg_o}Unrecognized option: {fg_y}{arg_key}{rst}.");
+                return false;
+            }
+            if self.tag.is_some() {
+                eprintln!("{fg_o}Expecting a single application tag; unexpected argument {fg_y}{arg_key}{rst}.");
+                return false;
+            }
+            if !is_valid_app_tag(arg_key) {
+                eprintln!("{fg_o}Not a valid application tag: {fg_y}{arg_key}{rst}.");
+                return false;
+            }
+            self.tag = Some(arg_key.to_string());
+            args.skip(1);
+        }
+        if self.tag.is_none() {
+            eprintln!("{fg_o}Expecting an application tag as argument{rst}.");
+            return false;
+        }
+        true
+    }
+}
+
+impl ShowCommand {
+    pub fn new() -> ShowCommand {
+        ShowCommand {
+            names: vec!["/show"],
+        }
+    }
+}
+
+/// Format a list of values for display, quoting each of them.
+fn format_list(values: &[String]) -> String {
+    if values.is_empty() {
+        return format!("{fg_k}(none){rst}");
+    }
+    values
+        .iter()
+        .map(|v| format!("{fg_c}\"{v}\"{rst}"))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Print the profiles of an application and which one is selected.
+fn print_profiles(cfg: &WexeAppConfig, requested: Option<&str>) {
+    let names = cfg.profile_names();
+    if names.is_empty() {
+        println!("{fg_W}{:<14}{rst} {fg_k}(none){rst}", "Profiles");
+        return;
+    }
+    let selected = cfg.select_profile(requested).ok().flatten();
+    for (index, name) in names.iter().enumerate() {
+        let label = if index == 0 { "Profiles" } else { "" };
+        if selected.as_ref() != Some(name) {
+            println!("{fg_W}{label:<14}{rst} {fg_k}[profiles.{name}]{rst}");
+            continue;
+        }
+        let source = if requested.is_some() {
+            "--profile".to_string()
+        } else if cfg.default_profile.as_ref() == Some(name)
+            && env::var(WEXE_PROFILE_VAR).ok().as_ref() != Some(name)
+        {
+            "default_profile".to_string()
+        } else {
+            WEXE_PROFILE_VAR.to_string()
+        };
+        println!("{fg_W}{label:<14}{rst} {fg_g}[profiles.{name}]{rst} (selected by {fg_y}{source}{rst})");
+    }
+}
+
+/// Print the resolved application model.
+fn print_app(app: &WexeApp) {
+    if Path::new(&app.target).exists() {
+        println!("{fg_W}{:<14}{rst} {fg_g}{}{rst}", "Target", app.target);
+    } else {
+        println!("{fg_W}{:<14}{rst} {fg_r}{}{rst} {fg_o}(missing){rst}", "Target", app.target);
+    }
+    println!("{fg_W}{:<14}{rst} {}", "Args prepend", format_list(&app.args.prepend));
+    println!("{fg_W}{:<14}{rst} {}", "Args append", format_list(&app.args.append));
+    for (index, rule) in app.args_rewrite.iter().enumerate() {
+        let label = if index == 0 { "Args rewrite" } else { "" };
+        let description = match (&rule.pattern, &rule.replace, &rule.insert) {
+            (Some(pattern), Some(replace), _) => {
+                format!("replace {fg_c}{pattern}{rst} by {fg_c}{replace}{rst}")
+            }
+            (Some(pattern), None, _) => format!("drop {fg_c}{pattern}{rst}"),
+            (None, _, Some(insert)) => match &rule.unless {
+                Some(unless) => format!("insert {fg_c}{insert}{rst} unless {fg_c}{unless}{rst}"),
+                None => format!("insert {fg_c}{insert}{rst} if absent"),
+            },
+            _ => format!("{fg_r}(invalid){rst}"),
+        };
+        println!("{fg_W}{label:<14}{rst} {}. {description}", index + 1);
+    }
+    if let Some(paths) = &app.args_paths {
+        let mut kinds = Vec::new();
+        if paths.existing.unwrap_or(false) {
+            kinds.push("existing files".to_string());
+        }
+        kinds.extend(paths.patterns.iter().flatten().map(|p| format!("{fg_c}{p}{rst}")));
+        let action = if paths.absolute.unwrap_or(true) { "absolutize" } else { "keep" };
+        println!("{fg_W}{:<14}{rst} {action} {}", "Args paths", kinds.join(", "));
+        for mapping in paths.map.iter().flatten() {
+            let separator = match &mapping.separator {
+                Some(separator) => format!(" (separator {fg_c}\"{separator}\"{rst})"),
+                None => String::new(),
+            };
+            println!(
+                "{:<14} map {fg_c}\"{}\"{rst} to {fg_c}\"{}\"{rst}{separator}",
+                "", mapping.from, mapping.to
+            );
+        }
+    }
+    if app.env_clear {
+        let keep = format_list(&app.env_keep);
+        println!("{fg_W}{:<14}{rst} {fg_o}cleared{rst}, keeping {keep}", "Env inherited");
+    }
+    let mut names: Vec<&String> = app.env_set.keys().collect();
+    names.sort();
+    for (index, name) in names.into_iter().enumerate() {
+        let label = if index == 0 { "Env set" } else { "" };
+        let value = &app.env_set[name];
+        if value.is_empty() {
+            println!("{fg_W}{label:<14}{rst} {fg_y}{name}{rst} {fg_o}(deleted){rst}");
+        } else {
+            println!("{fg_W}{label:<14}{rst} {fg_y}{name}{rst} = {fg_c}\"{value}\"{rst}");
+        }
+    }
+    let mut names: Vec<&String> = app.env_pathlike.keys().collect();
+    names.sort();
+    for (index, name) in names.into_iter().enumerate() {
+        let label = if index == 0 { "Env pathlike" } else { "" };
+        let ops = &app.env_pathlike[name];
+        println!(
+            "{fg_W}{label:<14}{rst} {fg_y}{name}{rst}: prepend {}, append {}",
+            format_list(&ops.prepend),
+            format_list(&ops.append)
+        );
+        if let Some(options) = app.env_pathlike_options.get(name) {
+            let flags = [
+                ("dedupe", options.dedupe),
+                ("drop_missing", options.drop_missing),
+                ("normalize", options.normalize),
+            ];
+            let set: Vec<&str> = flags.iter().filter(|(_, on)| *on).map(|(flag, _)| *flag).collect();
+            let width = name.len();
+            if !set.is_empty() {
+                println!("{:<14} {:width$}  {fg_g}{}{rst}", "", "", set.join(", "));
+            }
+            if !options.remove.is_empty() {
+                println!("{:<14} {:width$}  remove {}", "", "", format_list(&options.remove));
+            }
+        }
+    }
+}
+
+/// Print the complete environment the target of an application would run with.
+fn print_effective_env(launcher: Launcher) -> Result<ExitCode, Box<dyn Error>> {
+    let vars = match launcher.effective_env() {
+        Ok(vars) => vars,
+        Err(e) => {
+            println!("{fg_r}Environment error: {fg_o}{e}{rst}");
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+    if vars.is_empty() {
+        println!("{fg_W}{:<14}{rst} {fg_k}(empty){rst}", "Effective env");
+    }
+    for (index, (name, value)) in vars.iter().enumerate() {
+        let label = if index == 0 { "Effective env" } else { "" };
+        let (name, value) = (name.to_string_lossy(), value.to_string_lossy());
+        println!("{fg_W}{label:<14}{rst} {fg_y}{name}{rst}={fg_c}{value}{rst}");
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+impl Command for ShowCommand {
+    fn name(&self) -> &str {
+        self.names[0]
+    }
+
+    fn name_and_aliases(&self) -> &[&str] {
+        self.names.as_ref()
+    }
+
+    fn execute(
+        &self,
+        args: &mut ArgumentsBuffer,
+        commands: &CommandCollection,
+    ) -> Result<ExitCode, Box<dyn Error>> {
+        let mut options = ShowCommandOptions::new();
+        if !options.parse_args(args) {
+            commands.print_help_for(self.name());
+            return Ok(ExitCode::FAILURE);
+        }
+        let tag = options.tag.unwrap();
+        let repo = WexeRepository::new();
+        let cfg_path = match repo.find_entry(&tag) {
+            Some(entry) => entry.get_cfg_path().clone(),
+            None => {
+                eprintln!("{fg_r}No such application: {fg_y}{tag}{rst}.");
+                return Ok(ExitCode::FAILURE);
+            }
+        };
+        println!("{fg_W}{:<14}{rst} {fg_y}{tag}{rst}", "Application");
+        println!("{fg_W}{:<14}{rst} {fg_c}{}{rst}", "Config file", cfg_path.to_string_lossy());
+        let cfg = match read_app_config(&cfg_path) {
+            Ok(cfg) => cfg,
+            Err(e) => {
+                println!("{fg_r}Configuration error: {fg_o}{e}{rst}");
+                return Ok(ExitCode::FAILURE);
+            }
+        };
+        let sections = cfg.os_sections(env::consts::OS);
+        if sections.is_empty() {
+            println!("{fg_W}{:<14}{rst} {fg_k}(none){rst}", "OS sections");
+        }
+        for (index, (name, matched)) in sections.iter().enumerate() {
+            let label = if index == 0 { "OS sections" } else { "" };
+            if *matched {
+                println!("{fg_W}{label:<14}{rst} {fg_g}[os.{name}]{rst} (applied)");
+            } else {
+                println!("{fg_W}{label:<14}{rst} {fg_k}[os.{name}] (not this system){rst}");
+            }
+        }
+        print_profiles(&cfg, options.profile.as_deref());
+        let settings = read_wexe_settings().unwrap_or_default();
+        match LaunchLog::for_app(cfg.log.as_deref(), &settings) {
+            Some(log) => {
+                let source = if cfg.log.is_some() { "log" } else { "settings" };
+                let path = log.path.to_string_lossy();
+                println!("{fg_W}{:<14}{rst} {fg_c}{path}{rst} (from {fg_y}{source}{rst})", "Launch log");
+            }
+            None => println!("{fg_W}{:<14}{rst} {fg_k}(none){rst}", "Launch log"),
+        }
+        match LaunchTimeout::for_app(cfg.timeout.as_deref(), cfg.kill_after.as_deref()) {
+            Ok(Some(timeout)) => println!(
+                "{fg_W}{:<14}{rst} {fg_y}{}{rst}, killed {fg_y}{}{rst} after SIGTERM \
+                (exit code {fg_y}{TIMEOUT_EXIT_CODE}{rst})",
+                "Timeout",
+                format_duration(timeout.timeout),
+                format_duration(timeout.kill_after)
+            ),
+            Ok(None) => println!("{fg_W}{:<14}{rst} {fg_k}(none){rst}", "Timeout"),
+            Err(e) => println!("{fg_W}{:<14}{rst} {fg_r}{e}{rst}", "Timeout"),
+        }
+        match cfg.retry.as_ref().map(RetryPolicy::from_config) {
+            Some(Ok(policy)) => {
+                let codes = if policy.on_exit_codes.is_empty() {
+                    "any non-zero exit code".to_string()
+                } else {
+                    format!("exit codes {:?}", policy.on_exit_codes)
+                };
+                let signal = if policy.on_signal { " or a signal" } else { "" };
+                let backoff = match policy.backoff {
+                    Backoff::Fixed => "fixed",
+                    Backoff::Exponential => "exponential",
+                };
+                println!(
+                    "{fg_W}{:<14}{rst} up to {fg_y}{}{rst} attempts on {fg_y}{codes}{signal}{rst}, \
+                    {fg_y}{backoff}{rst} backoff from {fg_y}{}{rst}",
+                    "Retry",
+                    policy.attempts,
+                    format_duration(policy.delay)
+                );
+            }
+            Some(Err(e)) => println!("{fg_W}{:<14}{rst} {fg_r}{e}{rst}", "Retry"),
+            None => println!("{fg_W}{:<14}{rst} {fg_k}(none){rst}", "Retry"),
+        }
+        match read_config_file_with_profile(cfg_path, options.profile.as_deref()) {
+            Ok(app) => {
+                print_app(&app);
+                if options.effective {
+                    return print_effective_env(Launcher::new(app));
+                }
+                Ok(ExitCode::SUCCESS)
+            }
+            Err(e) => {
+                println!("{fg_r}Configuration error: {fg_o}{e}{rst}");
+                Ok(ExitCode::FAILURE)
+            }
+        }
+    }
+}
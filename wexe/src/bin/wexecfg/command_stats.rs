@@ -0,0 +1,199 @@
+use std::error::Error;
+use std::process::ExitCode;
+
+use chrono::{DateTime, Local, Utc};
+
+use super::args_buffer::ArgumentsBuffer;
+use super::commands::{Command, CommandCollection};
+use super::wexe_repository::WexeRepository;
+
+use wexe::config_model::read_wexe_settings;
+use wexe::console_colors::*;
+use wexe::usage_stats::{AppStats, get_wexe_stats_file, read_usage_stats};
+
+/// Applications not used for this many days are highlighted, unless `--unused` says otherwise
+pub const DEFAULT_UNUSED_DAYS: i64 = 30;
+
+pub struct StatsCommand {
+    names: Vec<&'static str>,
+}
+
+struct StatsCommandOptions {
+    pub filter: Option<String>,
+    pub unused_days: i64,
+}
+
+impl StatsCommand {
+    pub fn new() -> StatsCommand {
+        StatsCommand {
+            names: vec!["/stats"],
+        }
+    }
+}
+
+/// Parse the argument of the `--unused` option (shared with `/list --stats`)
+pub fn parse_unused_days(args: &mut ArgumentsBuffer) -> Option<i64> {
+    if args.remaining() < 2 {
+        eprintln!("{fg_o}Option {fg_y}--unused{fg_o} requires an argument.{rst}");
+        return None;
+    }
+    let value = args.get_at(1).to_string();
+    args.skip(2);
+    match value.parse::<i64>() {
+        Ok(days) if days >= 0 => Some(days),
+        _ => {
+            eprintln!("{fg_o}Expecting a number of days for {fg_y}--unused{fg_o}, not {fg_y}{value}{rst}.");
+            None
+        }
+    }
+}
+
+impl StatsCommandOptions {
+    pub fn new() -> StatsCommandOptions {
+        StatsCommandOptions {
+            filter: None,
+            unused_days: DEFAULT_UNUSED_DAYS,
+        }
+    }
+
+    pub fn parse_args(&mut self, args: &mut ArgumentsBuffer) -> bool {
+        while !args.is_empty() {
+            let arg_key = args.get();
+            match arg_key {
+                "-m" => {
+                    if args.remaining() < 2 {
+                        eprintln!("{fg_o}Option {fg_y}-m{fg_o} requires an argument.{rst}",);
+                        return false;
+                    }
+                    self.filter = Some(args.get_at(1).to_string());
+                    args.skip(2);
+                }
+                "--unused" => match parse_unused_days(args) {
+                    Some(days) => self.unused_days = days,
+                    None => return false,
+                },
+                _ => {
+                    eprintln!("{fg_o}Unrecognized option: {fg_y}{:}{rst}.", arg_key);
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Whether an application counts as unused: never launched, or not for `unused_days` days
+pub fn is_unused(stats: Option<&AppStats>, now: DateTime<Utc>, unused_days: i64) -> bool {
+    match stats.and_then(|stats| stats.days_unused(now)) {
+        Some(days) => days >= unused_days,
+        None => true,
+    }
+}
+
+/// The style and text of the "last used" column for an application
+pub fn last_used_column(
+    stats: Option<&AppStats>,
+    now: DateTime<Utc>,
+    unused_days: i64,
+) -> (String, String) {
+    let style = if is_unused(stats, now, unused_days) {
+        format!("{fg_o}{stl_i}")
+    } else {
+        fg_y.to_string()
+    };
+    let text = match stats.and_then(|stats| stats.last_used_time()) {
+        Some(time) => time.with_timezone(&Local).format("%Y%m%d-%H%M%S").to_string(),
+        None => "never".to_string(),
+    };
+    (style, text)
+}
+
+/// Format a runtime in milliseconds as hours, minutes and seconds
+fn format_runtime(runtime_ms: u64) -> String {
+    let seconds = runtime_ms / 1000;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}h{minutes:02}m{seconds:02}s")
+    } else if minutes > 0 {
+        format!("{minutes}m{seconds:02}s")
+    } else {
+        format!("{}.{:03}s", seconds, runtime_ms % 1000)
+    }
+}
+
+impl Command for StatsCommand {
+    fn name(&self) -> &str {
+        self.names[0]
+    }
+
+    fn name_and_aliases(&self) -> &[&str] {
+        self.names.as_ref()
+    }
+
+    fn execute(
+        &self,
+        args: &mut ArgumentsBuffer,
+        commands: &CommandCollection,
+    ) -> Result<ExitCode, Box<dyn Error>> {
+        let mut options = StatsCommandOptions::new();
+        if !options.parse_args(args) {
+            commands.print_help_for(self.name());
+            return Ok(ExitCode::FAILURE);
+        }
+        let stats_file = get_wexe_stats_file();
+        let stats = read_usage_stats(&stats_file)?;
+        if !read_wexe_settings()?.stats.unwrap_or(false) {
+            println!(
+                "{fg_o}Usage statistics are not being recorded{rst}; enable them with \
+                {fg_y}wexecfg /settings stats true{rst}."
+            );
+        }
+        println!("Usage statistics ({fg_k}{}{rst}):", stats_file.to_string_lossy());
+        let title_tag = "Application";
+        let title_launches = "Launches";
+        let title_last_used = "Last used";
+        let title_runtime = "Runtime";
+        let title_failures = "Non-zero exits";
+        println!(
+            "{stl_u}{title_tag:<20} | {stl_u}{title_launches:>8} | {stl_u}{title_last_used:<16} | {stl_u}{title_runtime:>10} | {stl_u}{title_failures:>14}.{rst}"
+        );
+        let now = Utc::now();
+        let mut unused = Vec::new();
+        let repo = WexeRepository::new();
+        for app in repo.get_entries().iter() {
+            let tag = app.get_tag();
+            if let Some(filter) = &options.filter
+                && !tag.contains(filter)
+            {
+                continue;
+            }
+            let app_stats = stats.get(tag);
+            let (last_used_style, last_used) = last_used_column(app_stats, now, options.unused_days);
+            let tag_style = if is_unused(app_stats, now, options.unused_days) {
+                unused.push(tag.to_string());
+                format!("{fg_o}{stl_i}")
+            } else {
+                fg_g.to_string()
+            };
+            let app_stats = app_stats.cloned().unwrap_or_default();
+            let failures_style = if app_stats.nonzero_exits > 0 { fg_r } else { fg_y };
+            let launches = app_stats.launches;
+            let runtime = format_runtime(app_stats.runtime_ms);
+            let failures = app_stats.nonzero_exits;
+            println!(
+                "{tag_style}{tag:<20}{rst} | {fg_y}{launches:>8}{rst} | {last_used_style}{last_used:<16}{rst} | {fg_y}{runtime:>10}{rst} | {failures_style}{failures:>14}{rst}"
+            );
+        }
+        if unused.is_empty() {
+            println!("All applications were used in the last {fg_y}{}{rst} days.", options.unused_days);
+        } else {
+            println!(
+                "{fg_o}{}{rst} application(s) not used in the last {fg_y}{}{rst} days: {fg_o}{}{rst}.",
+                unused.len(),
+                options.unused_days,
+                unused.join(", ")
+            );
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+}
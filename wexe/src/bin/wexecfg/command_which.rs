@@ -0,0 +1,214 @@
+use std::env;
+use std::env::consts::EXE_SUFFIX;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use same_file::is_same_file;
+
+use wexe::config_model::{config_file_search_path, is_valid_app_tag, read_config_file};
+use wexe::console_colors::*;
+
+use super::args_buffer::ArgumentsBuffer;
+use super::commands::{Command, CommandCollection};
+use super::wexe_repository::{WexeRepository, is_executable_file};
+
+pub struct WhichCommand {
+    names: Vec<&'static str>,
+}
+
+pub struct WhichCommandOptions {
+    pub command: Option<String>,
+}
+
+impl WhichCommandOptions {
+    pub fn new() -> WhichCommandOptions {
+        WhichCommandOptions { command: None }
+    }
+
+    pub fn parse_args(&mut self, args: &mut ArgumentsBuffer) -> bool {
+        while !args.is_empty() {
+            let arg_key = args.get();
+            if arg_key.starts_with('-') {
+                eprintln!("{fg_o}Unrecognized option: {fg_y}{arg_key}{rst}.");
+                return false;
+            }
+            if self.command.is_some() {
+                eprintln!("{fg_o}Expecting a single command name; unexpected argument {fg_y}{arg_key}{rst}.");
+                return false;
+            }
+            self.command = Some(arg_key.to_string());
+            args.skip(1);
+        }
+        if self.command.is_none() {
+            eprintln!("{fg_o}Expecting a command name as argument{rst}.");
+            return false;
+        }
+        true
+    }
+}
+
+impl WhichCommand {
+    pub fn new() -> WhichCommand {
+        WhichCommand {
+            names: vec!["/which"],
+        }
+    }
+}
+
+/// Find all executables named `command` on PATH, in PATH order.
+fn find_on_path(command: &str) -> Vec<PathBuf> {
+    let file_name = if command.ends_with(EXE_SUFFIX) {
+        command.to_string()
+    } else {
+        command.to_string() + EXE_SUFFIX
+    };
+    let mut found: Vec<PathBuf> = Vec::new();
+    if let Some(path) = env::var_os("PATH") {
+        for folder in env::split_paths(&path) {
+            if folder.as_os_str().is_empty() {
+                continue;
+            }
+            let candidate = folder.join(&file_name);
+            if is_executable_file(&candidate)
+                && !found
+                    .iter()
+                    .any(|f| is_same_file(f, &candidate).unwrap_or(false))
+            {
+                found.push(candidate);
+            }
+        }
+    }
+    found
+}
+
+/// Returns true if the executable looks like a wexe stub for the given tag: it lives in the
+/// wexe folder, is the installed wexe executable itself, or has a configuration file next to it.
+fn is_wexe_stub(repo: &WexeRepository, exe: &Path, tag: &str) -> bool {
+    let folder = exe.parent().unwrap_or(Path::new("."));
+    is_same_file(folder, repo.get_config_folder()).unwrap_or(false)
+        || is_same_file(exe, repo.get_wexe_exe_path()).unwrap_or(false)
+        || folder.join(tag.to_string() + ".toml").exists()
+}
+
+/// Print how the configuration for `tag` is resolved for a stub in `stub_folder`.
+/// Returns false if no usable configuration was found.
+fn explain_config(tag: &str, stub_folder: &Path) -> bool {
+    let candidates = config_file_search_path(tag, stub_folder);
+    let used = candidates.iter().position(|c| c.exists());
+    for (index, candidate) in candidates.iter().enumerate() {
+        let label = if index == 0 { "Config search" } else { "" };
+        let status = match used {
+            Some(u) if u == index => format!("{fg_g}(used){rst}"),
+            Some(u) if u < index => format!("{fg_o}(overridden){rst}"),
+            _ => format!("{fg_k}(missing){rst}"),
+        };
+        println!(
+            "{fg_W}{label:<14}{rst} {}. {fg_c}{}{rst} {status}",
+            index + 1,
+            candidate.to_string_lossy()
+        );
+    }
+    let cfg_file = match used {
+        Some(index) => &candidates[index],
+        None => {
+            println!("{fg_r}No configuration file found for {fg_y}{tag}{rst}.");
+            return false;
+        }
+    };
+    if used == Some(0) && candidates.len() > 1 && candidates[1].exists() {
+        println!(
+            "{fg_y}The configuration next to the stub overrides the one in the wexe folder{rst}."
+        );
+    }
+    match read_config_file(cfg_file.clone()) {
+        Ok(app) => {
+            let target = PathBuf::from(&app.target);
+            if target.exists() {
+                println!("{fg_W}{:<14}{rst} {fg_g}{}{rst}", "Target", app.target);
+            } else {
+                println!(
+                    "{fg_W}{:<14}{rst} {fg_r}{}{rst} {fg_o}(missing){rst}",
+                    "Target", app.target
+                );
+            }
+            target.exists()
+        }
+        Err(e) => {
+            println!("{fg_W}{:<14}{rst} {fg_r}Configuration error: {fg_o}{e}{rst}", "Target");
+            false
+        }
+    }
+}
+
+impl Command for WhichCommand {
+    fn name(&self) -> &str {
+        self.names[0]
+    }
+
+    fn name_and_aliases(&self) -> &[&str] {
+        self.names.as_ref()
+    }
+
+    fn execute(
+        &self,
+        args: &mut ArgumentsBuffer,
+        commands: &CommandCollection,
+    ) -> Result<ExitCode, Box<dyn Error>> {
+        let mut options = WhichCommandOptions::new();
+        if !options.parse_args(args) {
+            commands.print_help_for(self.name());
+            return Ok(ExitCode::FAILURE);
+        }
+        let command = options.command.unwrap();
+        let tag = command
+            .strip_suffix(EXE_SUFFIX)
+            .unwrap_or(&command)
+            .to_lowercase();
+        let repo = WexeRepository::new();
+        let found = find_on_path(&command);
+
+        println!("{fg_W}{:<14}{rst} {fg_y}{command}{rst}", "Command");
+        let stub_folder = match found.first() {
+            Some(exe) => {
+                let is_stub = is_valid_app_tag(&tag) && is_wexe_stub(&repo, exe, &tag);
+                let kind = if is_stub {
+                    format!("{fg_g}(wexe stub){rst}")
+                } else {
+                    format!("{fg_o}(not a wexe stub){rst}")
+                };
+                println!(
+                    "{fg_W}{:<14}{rst} {fg_c}{}{rst} {kind}",
+                    "Found on PATH",
+                    exe.to_string_lossy()
+                );
+                for other in found.iter().skip(1) {
+                    println!(
+                        "{fg_W}{:<14}{rst} {fg_k}{} (hidden){rst}",
+                        "Also on PATH",
+                        other.to_string_lossy()
+                    );
+                }
+                if !is_stub {
+                    return Ok(ExitCode::SUCCESS);
+                }
+                exe.parent().unwrap_or(Path::new(".")).to_path_buf()
+            }
+            None => {
+                println!("{fg_W}{:<14}{rst} {fg_o}Not found on PATH{rst}", "Found on PATH");
+                if !is_valid_app_tag(&tag) || repo.find_entry(&tag).is_none() {
+                    return Ok(ExitCode::FAILURE);
+                }
+                println!(
+                    "{fg_y}There is an application named {fg_g}{tag}{fg_y}, but its stub is not reachable through PATH{rst}."
+                );
+                repo.get_config_folder().clone()
+            }
+        };
+        if explain_config(&tag, &stub_folder) {
+            Ok(ExitCode::SUCCESS)
+        } else {
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
@@ -3,14 +3,12 @@ use std::fs;
 use std::path::PathBuf;
 use std::process::ExitCode;
 
-use toml_edit::DocumentMut;
-
-use wexe::config_model::is_valid_app_tag;
+use wexe::config_model::{WexeApp, app_to_toml, is_valid_app_tag};
 use wexe::console_colors::*;
 
 use super::args_buffer::ArgumentsBuffer;
 use super::commands::{Command, CommandCollection};
-use super::wexe_repository::WexeRepository;
+use super::wexe_repository::{StubKind, WexeRepository};
 
 pub struct WrapCommand {
     names: Vec<&'static str>,
@@ -22,6 +20,8 @@ pub struct WrapCommandOptions {
     pub force: bool,
     pre_args: Vec<String>,
     pre_path: Vec<String>,
+    /// The kind of stub to create. None to use the stub strategy of the repository.
+    pub stub_kind: Option<StubKind>,
 }
 
 impl WrapCommandOptions {
@@ -32,6 +32,7 @@ impl WrapCommandOptions {
             force: false,
             pre_args: Vec::new(),
             pre_path: Vec::new(),
+            stub_kind: None,
         }
     }
 
@@ -69,10 +70,7 @@ impl WrapCommandOptions {
                             #[cfg(windows)]
                             if !target_path
                                 .extension()
-                                .expect("Expecting target file to have a '.exe' extension")
-                                .to_str()
-                                .unwrap()
-                                .eq_ignore_ascii_case("exe")
+                                .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
                             {
                                 eprintln!(
                                     "{fg_o}Target executable path {fg_y}{target}{fg_o} does not have an '.exe' extension.{rst}",
@@ -85,6 +83,13 @@ impl WrapCommandOptions {
                                 );
                                 return false;
                             }
+                            #[cfg(not(windows))]
+                            if !super::wexe_repository::is_executable_file(&target_path) {
+                                eprintln!(
+                                    "{fg_o}Target executable path {fg_y}{target}{fg_o} is not executable.{rst}",
+                                );
+                                return false;
+                            }
                             println!("DEBUG: Target path: {:}", target_path.to_string_lossy());
                             self.target_path = Some(target_path);
                         }
@@ -98,54 +103,24 @@ impl WrapCommandOptions {
                     args.skip(2);
                 }
                 "-a" | "-arg" | "-preargs" => {
-                    if args.remaining() < 2 {
-                        eprintln!(
-                            "{fg_o}Option {fg_y}{arg_key}{fg_o} requires an argument {fg_W}(the argument to prepend).{rst}",
-                        );
+                    if !self.parse_pre_arg(args) {
                         return false;
                     }
-                    let arg = args.get_at(1);
-                    self.pre_args.push(arg.to_string());
-                    args.skip(2);
                 }
                 "-p" | "-path" | "-prepath" => {
-                    if args.remaining() < 2 {
-                        eprintln!(
-                            "{fg_o}Option {fg_y}{arg_key}{fg_o} requires an argument {fg_W}(the path to prepend to PATH){rst}.",
-                        );
+                    if !self.parse_pre_path(args) {
                         return false;
                     }
-                    let path = args.get_at(1);
-                    match std::path::absolute(path) {
-                        Ok(path) => {
-                            let path_txt = path.to_string_lossy();
-                            if !path.is_dir() {
-                                if !path.exists() {
-                                    eprintln!(
-                                        "{fg_r}{arg_key}{rst} {fg_y}{path_txt}{fg_o}: Path does not exist{rst}.",
-                                    );
-                                } else {
-                                    eprintln!(
-                                        "{fg_r}{arg_key}{rst} {fg_y}{path_txt}{fg_o}: Path is not a directory{rst}.",
-                                    );
-                                }
-                                return false;
-                            }
-                            self.pre_path.push(path_txt.to_string());
-                        }
-                        Err(e) => {
-                            eprintln!(
-                                "{fg_o}Error resolving absolute path to prepend to PATH {fg_y}{path}{fg_o}: {fg_R}{e}{rst}."
-                            );
-                            return false;
-                        }
-                    }
-                    args.skip(2);
                 }
                 "-F" | "-force" | "--force" => {
                     self.force = true;
                     args.skip(1);
                 }
+                "-stub-kind" | "--stub-kind" => {
+                    if !self.parse_stub_kind(args) {
+                        return false;
+                    }
+                }
                 _ => {
                     eprintln!("{fg_o}Unrecognized option: {fg_y}{:}{rst}.", arg_key);
                     return false;
@@ -161,6 +136,98 @@ impl WrapCommandOptions {
         true
     }
 
+    /// Parse the argument of an '-a' option (an extra argument to prepend), including
+    /// the option itself.
+    pub fn parse_pre_arg(&mut self, args: &mut ArgumentsBuffer) -> bool {
+        let arg_key = args.get();
+        if args.remaining() < 2 {
+            eprintln!(
+                "{fg_o}Option {fg_y}{arg_key}{fg_o} requires an argument {fg_W}(the argument to prepend).{rst}",
+            );
+            return false;
+        }
+        let arg = args.get_at(1);
+        self.pre_args.push(arg.to_string());
+        args.skip(2);
+        true
+    }
+
+    /// Parse the argument of a '-p' option (an extra directory to prepend to PATH),
+    /// including the option itself.
+    pub fn parse_pre_path(&mut self, args: &mut ArgumentsBuffer) -> bool {
+        let arg_key = args.get();
+        if args.remaining() < 2 {
+            eprintln!(
+                "{fg_o}Option {fg_y}{arg_key}{fg_o} requires an argument {fg_W}(the path to prepend to PATH){rst}.",
+            );
+            return false;
+        }
+        let path = args.get_at(1);
+        match std::path::absolute(path) {
+            Ok(path) => {
+                let path_txt = path.to_string_lossy();
+                if !path.is_dir() {
+                    if !path.exists() {
+                        eprintln!(
+                            "{fg_r}{arg_key}{rst} {fg_y}{path_txt}{fg_o}: Path does not exist{rst}.",
+                        );
+                    } else {
+                        eprintln!(
+                            "{fg_r}{arg_key}{rst} {fg_y}{path_txt}{fg_o}: Path is not a directory{rst}.",
+                        );
+                    }
+                    return false;
+                }
+                self.pre_path.push(path_txt.to_string());
+            }
+            Err(e) => {
+                eprintln!(
+                    "{fg_o}Error resolving absolute path to prepend to PATH {fg_y}{path}{fg_o}: {fg_R}{e}{rst}."
+                );
+                return false;
+            }
+        }
+        args.skip(2);
+        true
+    }
+
+    /// Parse the argument of a '--stub-kind' option, including the option itself.
+    pub fn parse_stub_kind(&mut self, args: &mut ArgumentsBuffer) -> bool {
+        let arg_key = args.get();
+        if args.remaining() < 2 {
+            eprintln!(
+                "{fg_o}Option {fg_y}{arg_key}{fg_o} requires an argument {fg_W}(the kind of stub).{rst}",
+            );
+            return false;
+        }
+        let kind_name = args.get_at(1);
+        match StubKind::parse(kind_name) {
+            Some(kind) => self.stub_kind = Some(kind),
+            None => {
+                eprintln!(
+                    "{fg_o}Unknown stub kind {fg_y}{kind_name}{fg_o}; expecting {fg_y}copy{fg_o}, \
+                    {fg_y}hardlink{fg_o}, {fg_y}symlink{fg_o} or {fg_y}script{rst}."
+                );
+                return false;
+            }
+        }
+        args.skip(2);
+        true
+    }
+
+    /// Create options for wrapping the given target, sharing the prepended arguments,
+    /// PATH entries, stub kind and force flag of this instance. The tag is derived from the target.
+    pub fn for_target(&self, target_path: PathBuf) -> WrapCommandOptions {
+        WrapCommandOptions {
+            target_path: Some(target_path),
+            tag: None,
+            force: self.force,
+            pre_args: self.pre_args.clone(),
+            pre_path: self.pre_path.clone(),
+            stub_kind: self.stub_kind,
+        }
+    }
+
     /// Get a reference to the target path. Panics if the target path is not set.
     pub fn get_target_path(&self) -> &PathBuf {
         self.target_path.as_ref().expect("Target path not set.")
@@ -235,116 +302,112 @@ impl Command for WrapCommand {
             commands.print_help_for(self.name());
             return Ok(ExitCode::FAILURE);
         }
-        let target = options.get_target_path();
-        let target_name = target.to_string_lossy();
         let tag = options.get_tag()?;
-        println!(
-            "Building application configuration '{fg_g}{tag}{rst}' targetting '{fg_c}{target_name}{rst}'."
-        );
-
-        // First build the configuration file, handle an existing file only after that.
-        // let mut doc = DocumentMut::new();
-        // doc["target"] = toml_edit::value(target_name.as_ref());
-
-        let mut doc = r#"
-# Additional arguments to prepend or append to the given command line.
-[args]
-prepend = [ ]
-append = [ ]
-
-# Plain environment variables to set or delete
-[env.set]
-# FOO = 'bar'
-# BAR = ''
-
-# You can prepend or append items to PATH-like environment variables
-# For example: prepend or append to the PATH environment variable itself
-[env.pathlike.PATH]
-prepend = [ ]
-append = [ ]
-"#
-        .parse::<DocumentMut>()
-        .expect("invalid toml");
-        doc["target"] = toml_edit::value(target_name.as_ref());
-
-        let arg_prepend = doc["args"]["prepend"]
-            .as_array_mut()
-            .expect("args.prepend is not an array");
-        for arg in options.pre_args.iter() {
-            let mut v: toml_edit::Value = arg.into();
-            v.decor_mut().set_prefix("\n  ");
-            arg_prepend.push_formatted(v);
-        }
-        let path_prepend = doc["env"]["pathlike"]["PATH"]["prepend"]
-            .as_array_mut()
-            .expect("env.pathlike.PATH.prepend is not an array");
-        for path in options.pre_path.iter() {
-            let mut v: toml_edit::Value = path.into();
-            v.decor_mut().set_prefix("\n  ");
-            path_prepend.push_formatted(v);
+        let repo = WexeRepository::new();
+        if wrap_app(&repo, &options, &tag)? {
+            Ok(ExitCode::SUCCESS)
+        } else {
+            Ok(ExitCode::FAILURE)
         }
+    }
+}
 
-        let document_text = doc.to_string();
-        //println!("DEBUG: Document: \n{fg_b}{document_text}{rst}");
+/// Create the configuration file and stub for one application, as described by
+/// `options`, using `tag` as its name. An existing configuration is only replaced
+/// if `options.force` is set; otherwise the new configuration is left next to it
+/// as a candidate (`app.toml.tmp`).
+/// Returns false if no stub could be created because wexe itself is not installed.
+pub fn wrap_app(
+    repo: &WexeRepository,
+    options: &WrapCommandOptions,
+    tag: &str,
+) -> Result<bool, Box<dyn Error>> {
+    let target = options.get_target_path();
+    let target_name = target.to_string_lossy();
+    println!(
+        "Building application configuration '{fg_g}{tag}{rst}' targetting '{fg_c}{target_name}{rst}'."
+    );
 
-        let repo = WexeRepository::new();
-        let cfg_folder = repo.get_config_folder();
-        let final_file = cfg_folder.join(tag.as_str()).with_extension("toml");
-        let tmp_file = final_file.with_extension("toml.tmp");
-        let bak_file = final_file.with_extension("toml.bak");
-        // println!(
-        //     "Saving intermediate config file: {fg_b}{:}{rst}",
-        //     tmp_file.to_string_lossy()
-        // );
-        fs::write(&tmp_file, document_text)?;
+    // First build the configuration file, handle an existing file only after that.
+    let mut app = WexeApp::new(&target_name);
+    app.args.prepend = options.pre_args.clone();
+    if !options.pre_path.is_empty() {
+        let path_ops = app.env_pathlike.entry("PATH".to_string()).or_default();
+        path_ops.prepend = options.pre_path.clone();
+    }
+    let document_text = app_to_toml(&app);
 
-        // Only now check if an entry for the application already exists, and use the
-        // configuration accordingly.
-        let existing_entry = repo.find_entry(tag.as_str());
-        match existing_entry {
-            Some(_entry) => {
-                if options.force {
-                    println!(
-                        "{fg_y}An entry for application '{fg_g}{tag}{fg_y}' already exists{rst}.\
-                        \n{fg_o}Backing up and overwriting {fg_y}{}{rst}.",
-                        final_file.to_string_lossy()
-                    );
-                    fs::rename(&final_file, &bak_file)?;
-                    fs::rename(&tmp_file, &final_file)?;
-                } else {
-                    println!(
-                        "{fg_y}An entry for application '{fg_g}{tag}{fg_y}' already exists{rst}.\
-                        \nNot overwriting it; new version is in {fg_b}{}{rst}.",
-                        tmp_file.to_string_lossy()
-                    );
-                }
-            }
-            None => {
-                println!("Saving {fg_g}{}{rst}", final_file.to_string_lossy());
+    let cfg_folder = repo.get_config_folder();
+    let final_file = cfg_folder.join(tag).with_extension("toml");
+    let tmp_file = final_file.with_extension("toml.tmp");
+    let bak_file = final_file.with_extension("toml.bak");
+    // println!(
+    //     "Saving intermediate config file: {fg_b}{:}{rst}",
+    //     tmp_file.to_string_lossy()
+    // );
+    fs::write(&tmp_file, document_text)?;
+
+    // Only now check if an entry for the application already exists, and use the
+    // configuration accordingly.
+    let existing_entry = repo.find_entry(tag);
+    match existing_entry {
+        Some(_entry) => {
+            if options.force {
+                println!(
+                    "{fg_y}An entry for application '{fg_g}{tag}{fg_y}' already exists{rst}.\
+                    \n{fg_o}Backing up and overwriting {fg_y}{}{rst}.",
+                    final_file.to_string_lossy()
+                );
+                fs::rename(&final_file, &bak_file)?;
                 fs::rename(&tmp_file, &final_file)?;
+            } else {
+                println!(
+                    "{fg_y}An entry for application '{fg_g}{tag}{fg_y}' already exists{rst}.\
+                    \nNot overwriting it; new version is in {fg_b}{}{rst}.",
+                    tmp_file.to_string_lossy()
+                );
             }
         }
-        let exe_file = final_file.with_extension("exe");
-        let wexe_file = repo.get_wexe_exe_path();
-        if !wexe_file.exists() {
-            eprintln!(
-                "{fg_o}The wexe executable file {fg_y}{}{fg_o} is not installed; cannot copy it{rst}.",
-                wexe_file.to_string_lossy()
+        None => {
+            println!("Saving {fg_g}{}{rst}", final_file.to_string_lossy());
+            fs::rename(&tmp_file, &final_file)?;
+        }
+    }
+    let exe_file = repo.get_stub_path(tag);
+    let wexe_file = repo.get_wexe_exe_path();
+    if !wexe_file.exists() {
+        eprintln!(
+            "{fg_o}The wexe executable file {fg_y}{}{fg_o} is not installed; cannot copy it{rst}.",
+            wexe_file.to_string_lossy()
+        );
+        return Ok(false);
+    }
+    let existing_kind = repo.get_stub_kind(&exe_file);
+    match (existing_kind, options.stub_kind) {
+        (Some(existing), Some(kind)) if existing != kind => {
+            println!(
+                "{fg_y}Converting existing executable file {fg_c}{}{fg_y} from {} to {}{rst}.",
+                exe_file.to_string_lossy(),
+                existing.name(),
+                kind.name()
             );
-            return Ok(ExitCode::FAILURE);
+            repo.create_stub(&exe_file, kind)?;
         }
-        if exe_file.exists() {
+        (Some(_), _) => {
             println!(
                 "{fg_y}Updating existing executable file {fg_c}{}{rst}.",
                 exe_file.to_string_lossy()
             );
-        } else {
+        }
+        (None, kind) => {
+            let kind = kind.unwrap_or(repo.get_stub_strategy());
             println!(
-                "{fg_y}Creating application executable {fg_g}{}{rst}.",
-                exe_file.to_string_lossy()
+                "{fg_y}Creating application executable {fg_g}{}{fg_y} ({}){rst}.",
+                exe_file.to_string_lossy(),
+                kind.name()
             );
-            fs::copy(&wexe_file, &exe_file)?;
+            repo.create_stub(&exe_file, kind)?;
         }
-        Ok(ExitCode::SUCCESS)
     }
+    Ok(true)
 }
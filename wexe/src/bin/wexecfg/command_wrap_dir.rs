@@ -0,0 +1,365 @@
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use glob::Pattern;
+
+use wexe::config_model::is_valid_app_tag;
+use wexe::console_colors::*;
+
+use super::args_buffer::ArgumentsBuffer;
+use super::command_wrap::{WrapCommandOptions, wrap_app};
+use super::commands::{Command, CommandCollection};
+use super::wexe_repository::{WexeRepository, is_executable_file};
+
+pub struct WrapDirCommand {
+    names: Vec<&'static str>,
+}
+
+pub struct WrapDirCommandOptions {
+    folder: Option<PathBuf>,
+    includes: Vec<Pattern>,
+    excludes: Vec<Pattern>,
+    prefix: String,
+    suffix: String,
+    dry_run: bool,
+    /// Options shared by all wrapped applications (-a, -p and -F)
+    shared: WrapCommandOptions,
+}
+
+/// What to do with a single file in the folder being wrapped
+enum WrapDirAction {
+    /// Create a new application
+    New,
+    /// Replace the configuration of an existing application (-F was given)
+    Overwrite,
+    /// An application with the same tag already exists; leave it alone
+    Conflict,
+    /// Do not wrap this file, for the given reason
+    Skip(String),
+}
+
+struct WrapDirCandidate {
+    target_path: PathBuf,
+    tag: Option<String>,
+    action: WrapDirAction,
+}
+
+impl WrapDirCommandOptions {
+    pub fn new() -> WrapDirCommandOptions {
+        WrapDirCommandOptions {
+            folder: None,
+            includes: Vec::new(),
+            excludes: Vec::new(),
+            prefix: String::new(),
+            suffix: String::new(),
+            dry_run: false,
+            shared: WrapCommandOptions::new(),
+        }
+    }
+
+    fn parse_pattern(args: &ArgumentsBuffer) -> Option<Pattern> {
+        let arg_key = args.get();
+        if args.remaining() < 2 {
+            eprintln!(
+                "{fg_o}Option {fg_y}{arg_key}{fg_o} requires an argument {fg_W}(a file name pattern).{rst}",
+            );
+            return None;
+        }
+        let pattern = args.get_at(1);
+        match Pattern::new(pattern) {
+            Ok(pattern) => Some(pattern),
+            Err(e) => {
+                eprintln!(
+                    "{fg_o}Invalid file name pattern {fg_y}{pattern}{fg_o}: {fg_R}{e}{rst}."
+                );
+                None
+            }
+        }
+    }
+
+    fn parse_affix(args: &ArgumentsBuffer) -> Option<String> {
+        let arg_key = args.get();
+        if args.remaining() < 2 {
+            eprintln!(
+                "{fg_o}Option {fg_y}{arg_key}{fg_o} requires an argument {fg_W}(the text to add to each tag).{rst}",
+            );
+            return None;
+        }
+        Some(args.get_at(1).to_lowercase())
+    }
+
+    pub fn parse_args(&mut self, args: &mut ArgumentsBuffer) -> bool {
+        while !args.is_empty() {
+            let arg_key = args.get();
+            match arg_key {
+                "-i" | "-include" => match Self::parse_pattern(args) {
+                    Some(pattern) => {
+                        self.includes.push(pattern);
+                        args.skip(2);
+                    }
+                    None => return false,
+                },
+                "-e" | "-exclude" => match Self::parse_pattern(args) {
+                    Some(pattern) => {
+                        self.excludes.push(pattern);
+                        args.skip(2);
+                    }
+                    None => return false,
+                },
+                "-prefix" => match Self::parse_affix(args) {
+                    Some(prefix) => {
+                        self.prefix = prefix;
+                        args.skip(2);
+                    }
+                    None => return false,
+                },
+                "-suffix" => match Self::parse_affix(args) {
+                    Some(suffix) => {
+                        self.suffix = suffix;
+                        args.skip(2);
+                    }
+                    None => return false,
+                },
+                "-a" | "-arg" | "-preargs" => {
+                    if !self.shared.parse_pre_arg(args) {
+                        return false;
+                    }
+                }
+                "-p" | "-path" | "-prepath" => {
+                    if !self.shared.parse_pre_path(args) {
+                        return false;
+                    }
+                }
+                "-F" | "-force" | "--force" => {
+                    self.shared.force = true;
+                    args.skip(1);
+                }
+                "-stub-kind" | "--stub-kind" => {
+                    if !self.shared.parse_stub_kind(args) {
+                        return false;
+                    }
+                }
+                "-dry-run" | "--dry-run" => {
+                    self.dry_run = true;
+                    args.skip(1);
+                }
+                folder => {
+                    if folder.starts_with('-') {
+                        eprintln!("{fg_o}Unrecognized option: {fg_y}{folder}{rst}.");
+                        return false;
+                    }
+                    if self.folder.is_some() {
+                        eprintln!(
+                            "{fg_o}Only one folder can be wrapped at a time; unexpected argument {fg_y}{folder}{rst}."
+                        );
+                        return false;
+                    }
+                    match std::path::absolute(folder) {
+                        Ok(path) => {
+                            if !path.is_dir() {
+                                eprintln!(
+                                    "{fg_o}Folder {fg_y}{folder}{fg_o} does not exist or is not a directory{rst}.",
+                                );
+                                return false;
+                            }
+                            self.folder = Some(path);
+                        }
+                        Err(e) => {
+                            eprintln!(
+                                "{fg_o}Error resolving folder path {fg_y}{folder}{fg_o}: {fg_R}{e}{rst}."
+                            );
+                            return false;
+                        }
+                    }
+                    args.skip(1);
+                }
+            }
+        }
+        if self.folder.is_none() {
+            eprintln!("{fg_o}No folder specified{rst}.");
+            return false;
+        }
+        true
+    }
+
+    /// Returns true if the file name passes the include and exclude patterns.
+    /// Without include patterns, all files are included.
+    fn is_selected(&self, file_name: &str) -> bool {
+        let included =
+            self.includes.is_empty() || self.includes.iter().any(|p| p.matches(file_name));
+        included && !self.excludes.iter().any(|p| p.matches(file_name))
+    }
+}
+
+/// Decide what to do with each selected file in the folder.
+fn plan_candidates(
+    repo: &WexeRepository,
+    options: &WrapDirCommandOptions,
+) -> Result<Vec<WrapDirCandidate>, Box<dyn Error>> {
+    let folder = options.folder.as_ref().expect("Folder not set.");
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for direntry in fs::read_dir(folder)? {
+        let path = direntry?.path();
+        let selected = match path.file_name() {
+            Some(name) => options.is_selected(&name.to_string_lossy()),
+            None => false,
+        };
+        if selected && path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut seen_tags: BTreeSet<String> = BTreeSet::new();
+    let mut candidates: Vec<WrapDirCandidate> = Vec::new();
+    for path in paths {
+        if !is_executable_file(&path) {
+            candidates.push(WrapDirCandidate {
+                target_path: path,
+                tag: None,
+                action: WrapDirAction::Skip("Not executable".into()),
+            });
+            continue;
+        }
+        let tag = match options.shared.for_target(path.clone()).get_tag() {
+            Ok(tag) => format!("{}{}{}", options.prefix, tag, options.suffix),
+            Err(_) => {
+                candidates.push(WrapDirCandidate {
+                    target_path: path,
+                    tag: None,
+                    action: WrapDirAction::Skip("No valid tag".into()),
+                });
+                continue;
+            }
+        };
+        let action = if !is_valid_app_tag(&tag) || tag == "wexe" || tag == "wexecfg" {
+            WrapDirAction::Skip("Invalid tag".into())
+        } else if seen_tags.contains(&tag) {
+            WrapDirAction::Skip("Duplicate tag".into())
+        } else if repo.find_entry(&tag).is_some() {
+            if options.shared.force {
+                WrapDirAction::Overwrite
+            } else {
+                WrapDirAction::Conflict
+            }
+        } else {
+            WrapDirAction::New
+        };
+        seen_tags.insert(tag.clone());
+        candidates.push(WrapDirCandidate {
+            target_path: path,
+            tag: Some(tag),
+            action,
+        });
+    }
+    Ok(candidates)
+}
+
+fn print_candidates(candidates: &[WrapDirCandidate]) {
+    let title_file = "File";
+    let title_tag = "Application";
+    let title_action = "Action";
+    println!("{stl_u}{title_file:<24} | {stl_u}{title_tag:<20} | {stl_u}{title_action:<30}{rst}");
+    for candidate in candidates.iter() {
+        let file_name = candidate
+            .target_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        let tag = candidate.tag.as_deref().unwrap_or("-");
+        let (style, action_text) = match &candidate.action {
+            WrapDirAction::New => (fg_g.to_string(), "Create".to_string()),
+            WrapDirAction::Overwrite => (fg_y.to_string(), "Overwrite existing".to_string()),
+            WrapDirAction::Conflict => (
+                format!("{fg_o}{stl_i}"),
+                "Conflict: application exists".to_string(),
+            ),
+            WrapDirAction::Skip(reason) => (format!("{fg_k}{stl_i}"), format!("Skip: {reason}")),
+        };
+        println!("{style}{file_name:<24}{rst} | {style}{tag:<20}{rst} | {style}{action_text}{rst}");
+    }
+}
+
+impl WrapDirCommand {
+    pub fn new() -> WrapDirCommand {
+        WrapDirCommand {
+            names: vec!["/wrap-dir", "/wd"],
+        }
+    }
+}
+
+impl Command for WrapDirCommand {
+    fn name(&self) -> &str {
+        self.names[0]
+    }
+
+    fn name_and_aliases(&self) -> &[&str] {
+        self.names.as_ref()
+    }
+
+    fn execute(
+        &self,
+        args: &mut ArgumentsBuffer,
+        commands: &CommandCollection,
+    ) -> Result<ExitCode, Box<dyn Error>> {
+        let mut options = WrapDirCommandOptions::new();
+        if !options.parse_args(args) {
+            commands.print_help_for(self.name());
+            return Ok(ExitCode::FAILURE);
+        }
+        let repo = WexeRepository::new();
+        let candidates = plan_candidates(&repo, &options)?;
+        print_candidates(&candidates);
+        if options.dry_run {
+            println!("{fg_y}Dry run{rst}: no applications were created.");
+            return Ok(ExitCode::SUCCESS);
+        }
+        let wexe_file = repo.get_wexe_exe_path();
+        if !wexe_file.exists() {
+            eprintln!(
+                "{fg_o}The wexe executable file {fg_y}{}{fg_o} is not installed; cannot create stubs{rst}.",
+                wexe_file.to_string_lossy()
+            );
+            return Ok(ExitCode::FAILURE);
+        }
+
+        let mut wrapped: Vec<&str> = Vec::new();
+        let mut conflicts: Vec<&str> = Vec::new();
+        let mut failures: Vec<&str> = Vec::new();
+        for candidate in candidates.iter() {
+            let tag = match (&candidate.tag, &candidate.action) {
+                (Some(tag), WrapDirAction::New | WrapDirAction::Overwrite) => tag.as_str(),
+                (Some(tag), WrapDirAction::Conflict) => {
+                    conflicts.push(tag.as_str());
+                    continue;
+                }
+                _ => continue,
+            };
+            let app_options = options.shared.for_target(candidate.target_path.clone());
+            match wrap_app(&repo, &app_options, tag) {
+                Ok(true) => wrapped.push(tag),
+                Ok(false) => failures.push(tag),
+                Err(e) => {
+                    eprintln!("{fg_r}{tag:>20}{fg_W} : {fg_o}Failed: {fg_R}{e}{rst}.");
+                    failures.push(tag);
+                }
+            }
+        }
+
+        println!("Wrapped {fg_g}{}{rst} application(s).", wrapped.len());
+        if !conflicts.is_empty() {
+            println!(
+                "{fg_o}Not replaced (already exist; use {fg_g}-F{fg_o} to overwrite){rst}: {fg_y}{}{rst}.",
+                conflicts.join(", ")
+            );
+        }
+        if !failures.is_empty() {
+            eprintln!("{fg_r}Failed{rst}: {fg_y}{}{rst}.", failures.join(", "));
+            return Ok(ExitCode::FAILURE);
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+}
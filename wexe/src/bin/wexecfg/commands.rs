@@ -34,12 +34,11 @@ pub struct CommandCollection {
 impl CommandCollection {
     /// Create a new, empty command collection.
     pub fn new() -> CommandCollection {
-        let commands = CommandCollection {
+        CommandCollection {
             commands: BTreeMap::new(),
             command_map: HashMap::new(),
             help_central: HelpCentral::new(),
-        };
-        commands
+        }
     }
 
     /// Add a command to the collection (moving the command into it).
@@ -53,9 +52,9 @@ impl CommandCollection {
     }
 
     /// Get a command by name or alias.
-    pub fn get_command(&self, name: &str) -> Option<&Box<dyn Command>> {
+    pub fn get_command(&self, name: &str) -> Option<&dyn Command> {
         match self.command_map.get(name) {
-            Some(primary_name) => self.commands.get(primary_name),
+            Some(primary_name) => self.commands.get(primary_name).map(|c| c.as_ref()),
             None => None,
         }
     }
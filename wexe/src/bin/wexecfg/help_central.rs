@@ -27,12 +27,47 @@ fn init_help() -> Vec<CommandHelp> {
     });
     help.push(CommandHelp {
         command: "/list".into(),
-        synopsis: format!("{fg_y}/list{rst} [{fg_g}-m {fg_c}{stl_i}filter{rst}]"),
+        synopsis: format!(
+            "{fg_y}/list{rst} [{fg_g}-m {fg_c}{stl_i}filter{rst}] [{fg_g}--stats{rst}] \
+            [{fg_g}--unused {fg_c}{stl_i}days{rst}]"
+        ),
         description: "List all configured applications".into(),
-        options: vec![format!(
-            "{fg_g}-m {fg_c}{stl_i}filter{rst}       If given, only \
+        options: vec![
+            format!(
+                "{fg_g}-m {fg_c}{stl_i}filter{rst}       If given, only \
                 list applications with the {fg_c}{stl_i}filter{rst} string in their name."
-        )],
+            ),
+            format!(
+                "{fg_g}--stats{rst}         Also show the number of launches and the last use \
+                of each application\n                    (see {fg_y}/stats{rst})."
+            ),
+            format!(
+                "{fg_g}--unused {fg_c}{stl_i}days{rst}   With {fg_g}--stats{rst}, highlight \
+                applications not used in this many days (default 30)."
+            ),
+        ],
+    });
+    help.push(CommandHelp {
+        command: "/stats".into(),
+        synopsis: format!(
+            "{fg_y}/stats{rst} [{fg_g}-m {fg_c}{stl_i}filter{rst}] [{fg_g}--unused {fg_c}{stl_i}days{rst}]"
+        ),
+        description: format!(
+            "Show the usage statistics of the applications: launches, last use, cumulative runtime \
+            and\n    the number of non-zero exit codes. Statistics are only recorded when the \
+            {fg_W}stats{rst} setting is on\n    (see {fg_y}/settings{rst}); they are kept in \
+            {fg_o}wexe.stats.json{rst} in the wexe configuration folder."
+        ),
+        options: vec![
+            format!(
+                "{fg_g}-m {fg_c}{stl_i}filter{rst}       If given, only \
+                show applications with the {fg_c}{stl_i}filter{rst} string in their name."
+            ),
+            format!(
+                "{fg_g}--unused {fg_c}{stl_i}days{rst}   Highlight applications not used in this \
+                many days, or never (default 30)."
+            ),
+        ],
     });
     help.push(CommandHelp {
         command: "/wrap".into(),
@@ -40,6 +75,7 @@ fn init_help() -> Vec<CommandHelp> {
             "{fg_y}/wrap {fg_g}-x {fg_c}{stl_i}target.exe{rst} [{fg_g}-n {fg_c}{stl_i}name{rst}] \
             {{{fg_g}-a {fg_c}{stl_i}argument{rst}}} \
             {{{fg_g}-p {fg_c}{stl_i}path{rst}}} \
+            [{fg_g}--stub-kind {fg_c}{stl_i}kind{rst}] \
             [{fg_g}-F{rst}]"),
         description: format!(
             "Create a new application for the target executable. Creates a configuration file and a stub \
@@ -47,9 +83,9 @@ fn init_help() -> Vec<CommandHelp> {
             \n    {fg_W}\u{2022} {stl_i}If the application configuration already exists, a candidate configuration \
             file ({fg_o}app.toml.tmp{fg_W})\
             \n      is created instead, unless {fg_g}-F{fg_W} is specified{rst}.\
-            \n    {fg_W}\u{2022} {stl_i}If the stub executable already exists, it is not replaced.{rst}."
-        )
-        .into(),
+            \n    {fg_W}\u{2022} {stl_i}If the stub executable already exists, it is not replaced, unless \
+            {fg_g}--stub-kind{fg_W} asks for another kind{rst}."
+        ),
         options: vec![format!(
             "{fg_g}-x {fg_c}{stl_i}target.exe{rst}   The path to the target executable. Also implies \
             the name of the application, unless {fg_g}-n{rst} is given."
@@ -63,18 +99,77 @@ fn init_help() -> Vec<CommandHelp> {
         format!(
             "{fg_g}-p {fg_c}{stl_i}path{rst}         ({stl_i}repeatable{rst}) Extra path to prepend to PATH."
         ),
+        format!(
+            "{fg_g}--stub-kind {fg_c}{stl_i}kind{rst} The kind of stub to create: {fg_y}copy{rst}, \
+            {fg_y}hardlink{rst}, {fg_y}symlink{rst} or {fg_y}script{rst}\n                    \
+            (a {fg_o}sh{rst} script running wexe; {stl_i}Unix only{rst}). Defaults to the \
+            {fg_y}stub_strategy{rst} setting."
+        ),
         format!(
             "{fg_g}-F{rst}              ('{stl_i}Force{rst}') If the configuration file already exists, overwrite it instead of \
             creating a candidate."
         )],
     });
+    help.push(CommandHelp {
+        command: "/wrap-dir".into(),
+        synopsis: format!(
+            "{fg_y}/wrap-dir {fg_c}{stl_i}folder{rst} \
+            {{{fg_g}-i {fg_c}{stl_i}glob{rst}}} \
+            {{{fg_g}-e {fg_c}{stl_i}glob{rst}}} \
+            [{fg_g}-prefix {fg_c}{stl_i}text{rst}] \
+            [{fg_g}-suffix {fg_c}{stl_i}text{rst}] \
+            {{{fg_g}-a {fg_c}{stl_i}argument{rst}}} \
+            {{{fg_g}-p {fg_c}{stl_i}path{rst}}} \
+            [{fg_g}--stub-kind {fg_c}{stl_i}kind{rst}] \
+            [{fg_g}-F{rst}] [{fg_g}--dry-run{rst}]"),
+        description: format!(
+            "Create applications for all executables in {fg_c}{stl_i}folder{rst}, as if {fg_y}/wrap{rst} \
+            was called for each of them.\
+            \n    {fg_W}\u{2022} {stl_i}Existing applications are reported as conflicts and left alone, \
+            unless {fg_g}-F{fg_W} is specified{rst}."
+        ),
+        options: vec![
+            format!(
+                "{fg_c}{stl_i}folder{rst}          The folder containing the target executables."
+            ),
+            format!(
+                "{fg_g}-i {fg_c}{stl_i}glob{rst}         ({stl_i}repeatable{rst}) Only include files \
+                matching the pattern (default: all files)."
+            ),
+            format!(
+                "{fg_g}-e {fg_c}{stl_i}glob{rst}         ({stl_i}repeatable{rst}) Exclude files matching \
+                the pattern."
+            ),
+            format!(
+                "{fg_g}-prefix {fg_c}{stl_i}text{rst}    Text to put in front of each derived application name."
+            ),
+            format!(
+                "{fg_g}-suffix {fg_c}{stl_i}text{rst}    Text to put after each derived application name."
+            ),
+            format!(
+                "{fg_g}-a {fg_c}{stl_i}argument{rst}     ({stl_i}repeatable{rst}) Extra command-line \
+                argument to prepend, for all applications."
+            ),
+            format!(
+                "{fg_g}-p {fg_c}{stl_i}path{rst}         ({stl_i}repeatable{rst}) Extra path to prepend \
+                to PATH, for all applications."
+            ),
+            format!(
+                "{fg_g}--stub-kind {fg_c}{stl_i}kind{rst} The kind of stub to create (see {fg_y}/wrap{rst})."
+            ),
+            format!(
+                "{fg_g}-F{rst}              ('{stl_i}Force{rst}') Overwrite existing application \
+                configurations."
+            ),
+            format!(
+                "{fg_g}--dry-run{rst}       Only show what would be done."
+            ),
+        ],
+    });
     help.push(CommandHelp {
         command: "/drop".into(),
         synopsis: format!("{fg_y}/drop{fg_W} {{{fg_c}{stl_i}app-name{rst}}}"),
-        description: format!(
-            "Removes the configuration and stub for the specified application(s)."
-        )
-        .into(),
+        description: "Removes the configuration and stub for the specified application(s).".into(),
         options: vec![
             format!("{fg_c}{stl_i}app-name{rst}        ({stl_i}repeatable{rst}) The name of the application to remove."),
         ],
@@ -83,14 +178,184 @@ fn init_help() -> Vec<CommandHelp> {
         command: "/fix".into(),
         synopsis: format!("{fg_y}/fix{fg_W} [{fg_g}-all{fg_W}|{fg_c}{stl_i}app-name{rst}]"),
         description: format!(
-            "Recreate the application stub for the specified application or all applications."
-        )
-        .into(),
+            "Recreate the application stub for the specified application or all applications.\
+            \n    {fg_W}\u{2022} {stl_i}Stubs that do not match the {fg_y}stub_strategy{fg_W} setting \
+            are converted to it;\
+            \n      script stubs are kept as scripts and regenerated{rst}."
+        ),
         options: vec![
             format!("{fg_g}-all{fg_W}            Update all application stubs."),
             format!("{fg_c}{stl_i}app-name{rst}        Update {fg_c}{stl_i}app-name{rst} only."),
         ],
     });
+    help.push(CommandHelp {
+        command: "/scan".into(),
+        synopsis: format!("{fg_y}/scan{rst} [{fg_g}-max {fg_c}{stl_i}count{rst}] [{fg_g}-json{rst}]"),
+        description: format!(
+            "Check the folders on {fg_o}PATH{rst} against the configured applications. Reports \
+            applications hidden by\n    or hiding another executable of the same name, and \
+            folders that could be dropped from {fg_o}PATH{rst} by wrapping\n    their executables."
+        ),
+        options: vec![
+            format!(
+                "{fg_g}-max {fg_c}{stl_i}count{rst}     Only suggest folders with at most \
+                {fg_c}{stl_i}count{rst} executables left to wrap (default 5)."
+            ),
+            format!("{fg_g}-json{rst}           Write the report as JSON instead of tables."),
+        ],
+    });
+    help.push(CommandHelp {
+        command: "/which".into(),
+        synopsis: format!("{fg_y}/which{rst} {fg_c}{stl_i}command{rst}"),
+        description: format!(
+            "Explain what running {fg_c}{stl_i}command{rst} resolves to: the executable found on \
+            {fg_o}PATH{rst} and, if that is a\n    wexe stub, the configuration file it uses and \
+            the target it runs."
+        ),
+        options: vec![format!(
+            "{fg_c}{stl_i}command{rst}         The command name to look up."
+        )],
+    });
+    help.push(CommandHelp {
+        command: "/show".into(),
+        synopsis: format!(
+            "{fg_y}/show{rst} {fg_c}{stl_i}app-name{rst} [{fg_g}--profile {fg_c}{stl_i}name{rst}] \
+            [{fg_g}--effective{rst}]"
+        ),
+        description: format!(
+            "Show the configuration of an application as it applies on this system: the \
+            {fg_o}[os.*]{rst} sections\n    that matched, the selected profile, and the resulting \
+            target, arguments and environment changes."
+        ),
+        options: vec![
+            format!("{fg_c}{stl_i}app-name{rst}        The application to show."),
+            format!(
+                "{fg_g}--profile {fg_c}{stl_i}name{rst}  Show the application with profile \
+                {fg_c}{stl_i}name{rst} selected, as {fg_y}+{fg_c}{stl_i}name{rst} as first \
+                argument would."
+            ),
+            format!(
+                "{fg_g}--effective{rst}       Also show the complete environment the target would \
+                run with (after {fg_o}env.clear{rst},\n                      {fg_o}env.keep{rst}, \
+                {fg_o}env.set{rst} and {fg_o}env.pathlike{rst} are applied)."
+            ),
+        ],
+    });
+    help.push(CommandHelp {
+        command: "/export".into(),
+        synopsis: format!("{fg_y}/export{rst} [{fg_g}--tags {fg_c}{stl_i}app-name{rst} {{{fg_c}{stl_i}app-name{rst}}}]"),
+        description: format!(
+            "Write the configuration of all (or the selected) applications to standard output as a \
+            single bundle,\n    to be loaded with {fg_y}/import{rst} on another machine."
+        ),
+        options: vec![format!(
+            "{fg_g}--tags {fg_c}{stl_i}app-name{rst}  ({stl_i}repeatable{rst}) Only export the named applications."
+        )],
+    });
+    help.push(CommandHelp {
+        command: "/import".into(),
+        synopsis: format!(
+            "{fg_y}/import{rst} {fg_c}{stl_i}bundle.toml{rst} \
+            [{fg_g}--merge{fg_W}|{fg_g}--overwrite{fg_W}|{fg_g}--skip-existing{rst}] \
+            [{fg_g}--from {fg_c}{stl_i}prefix{rst} {fg_g}--to {fg_c}{stl_i}prefix{rst}] [{fg_g}--dry-run{rst}]"
+        ),
+        description: format!(
+            "Create the applications in a bundle written by {fg_y}/export{rst}, including their stubs.\
+            \n    {fg_W}\u{2022} {stl_i}A report of the actions and of targets missing on this machine \
+            is shown first{rst}.\
+            \n    {fg_W}\u{2022} {stl_i}If any application already exists, one of the conflict options \
+            is required{rst}."
+        ),
+        options: vec![
+            format!(
+                "{fg_g}--merge{rst}         Merge into existing configurations; imported values win."
+            ),
+            format!(
+                "{fg_g}--overwrite{rst}     Replace existing configurations (a backup is kept)."
+            ),
+            format!("{fg_g}--skip-existing{rst} Leave existing configurations alone."),
+            format!(
+                "{fg_g}--from {fg_c}{stl_i}prefix{rst}   Rebase paths starting with {fg_c}{stl_i}prefix{rst} \
+                (requires {fg_g}--to{rst}; see {fg_y}/retarget{rst})."
+            ),
+            format!("{fg_g}--to {fg_c}{stl_i}prefix{rst}     The replacement for the {fg_g}--from{rst} prefix."),
+            format!("{fg_g}--dry-run{rst}       Only show the report."),
+        ],
+    });
+    help.push(CommandHelp {
+        command: "/retarget".into(),
+        synopsis: format!(
+            "{fg_y}/retarget {fg_g}--from {fg_c}{stl_i}prefix{rst} {fg_g}--to {fg_c}{stl_i}prefix{rst} \
+            [{fg_g}--tags {fg_c}{stl_i}app-name{rst} {{{fg_c}{stl_i}app-name{rst}}}] [{fg_g}--dry-run{rst}]"
+        ),
+        description: format!(
+            "Replace a path prefix in the configurations of all (or the selected) applications, \
+            e.g. after moving a toolchain.\
+            \n    {fg_W}\u{2022} {stl_i}Rewrites the target, {fg_o}env.pathlike{fg_W} entries, \
+            {fg_o}env.set{fg_W} values and path-like arguments, keeping comments{rst}.\
+            \n    {fg_W}\u{2022} {stl_i}Shows the changes, then checks that each new target exists{rst}."
+        ),
+        options: vec![
+            format!("{fg_g}--from {fg_c}{stl_i}prefix{rst}   The path prefix to replace."),
+            format!("{fg_g}--to {fg_c}{stl_i}prefix{rst}     The new path prefix."),
+            format!(
+                "{fg_g}--tags {fg_c}{stl_i}app-name{rst}  ({stl_i}repeatable{rst}) Only change the named applications."
+            ),
+            format!("{fg_g}--dry-run{rst}       Only show the changes."),
+        ],
+    });
+    help.push(CommandHelp {
+        command: "/migrate".into(),
+        synopsis: format!(
+            "{fg_y}/migrate{fg_W} [{fg_g}-all{fg_W}|{fg_c}{stl_i}app-name{rst} {{{fg_c}{stl_i}app-name{rst}}}] \
+            [{fg_g}--dry-run{rst}]"
+        ),
+        description: format!(
+            "Upgrade application configurations written for an older configuration {fg_o}schema{rst} \
+            to the current one.\
+            \n    {fg_W}\u{2022} {stl_i}Edits the files in place, keeping comments; a backup is kept{rst}.\
+            \n    {fg_W}\u{2022} {stl_i}Configurations for a newer schema are reported; they need a newer \
+            wexe{rst}."
+        ),
+        options: vec![
+            format!("{fg_g}-all{fg_W}            Upgrade all applications."),
+            format!("{fg_c}{stl_i}app-name{rst}        Upgrade {fg_c}{stl_i}app-name{rst} only."),
+            format!("{fg_g}--dry-run{rst}       Only show what would be upgraded."),
+        ],
+    });
+    help.push(CommandHelp {
+        command: "/settings".into(),
+        synopsis: format!("{fg_y}/settings{rst} [{fg_W}{stl_i}name{rst} {fg_c}{stl_i}value{rst}]"),
+        description: format!(
+            "Show the repository-wide settings, or change one of them. Settings are stored in \
+            {fg_o}wexe.settings.toml{rst}\n    in the wexe configuration folder."
+        ),
+        options: vec![
+            format!(
+                "{fg_W}stub_strategy {fg_c}{stl_i}kind{rst}  How stubs are created: {fg_y}copy{rst} \
+                (the default), {fg_y}hardlink{rst}, {fg_y}symlink{rst} or \
+                {fg_y}script{rst}\n                    ({stl_i}the last two are Unix only{rst})."
+            ),
+            format!(
+                "{fg_W}log {fg_c}{stl_i}path{rst}            Append a JSON record of each launch to \
+                {fg_c}{stl_i}path{rst} (relative to the wexe\n                    configuration \
+                folder), for the applications without their own {fg_y}log{rst} key."
+            ),
+            format!(
+                "{fg_W}log_max_size {fg_c}{stl_i}bytes{rst}  Rotate launch logs beyond this size \
+                (default 1048576; 0: never)."
+            ),
+            format!(
+                "{fg_W}log_keep {fg_c}{stl_i}count{rst}      How many rotated launch logs to keep \
+                (default 3)."
+            ),
+            format!(
+                "{fg_W}stats {fg_c}{stl_i}true|false{rst}    Count launches, runtime and non-zero \
+                exits per application in\n                    {fg_o}wexe.stats.json{rst} \
+                (default false; see {fg_y}/stats{rst})."
+            ),
+        ],
+    });
     help.push(CommandHelp {
         command: "/install".into(),
         synopsis: format!("{fg_y}/install{rst} [{fg_g}-wexe{rst}]"),
@@ -101,8 +366,7 @@ fn init_help() -> Vec<CommandHelp> {
             Use the debug or release build output instead{rst}.\
             \n    {fg_W}\u{2022} {stl_i}Does not replace stub executables (copies of {fg_o}wexe{fg_W}); use \
             {fg_o}wexecfg {fg_y}/fix{fg_W} for that{rst}."
-        )
-        .into(),
+        ),
         options: vec![format!(
             "{fg_g}-wexe{rst}           If given, also copies {fg_o}wexe.exe{rst} to the installation folder."
         )],
@@ -121,12 +385,7 @@ impl HelpCentral {
     }
 
     pub fn get_help(&self, command: &str) -> Option<&CommandHelp> {
-        for cmd in self.commands.iter() {
-            if cmd.command == command {
-                return Some(cmd);
-            }
-        }
-        None
+        self.commands.iter().find(|cmd| cmd.command == command)
     }
 
     pub fn print_help_for(&self, command: &str) {
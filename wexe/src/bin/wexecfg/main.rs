@@ -1,4 +1,5 @@
 use std::error::Error;
+use std::ffi::OsString;
 use std::process::ExitCode;
 
 use wexe::console_colors::*;
@@ -6,15 +7,27 @@ use wexe::console_colors::*;
 use args_buffer::ArgumentsBuffer;
 use commands::CommandCollection;
 
+mod app_bundle;
 mod args_buffer;
 mod command_drop;
+mod command_export;
 mod command_fix;
 mod command_help;
+mod command_import;
 mod command_install;
 mod command_list;
+mod command_migrate;
+mod command_retarget;
+mod command_scan;
+mod command_settings;
+mod command_show;
+mod command_stats;
+mod command_which;
 mod command_wrap;
+mod command_wrap_dir;
 mod commands;
 mod help_central;
+mod path_rebase;
 mod wexe_repository;
 
 fn setup_commands() -> CommandCollection {
@@ -23,16 +36,36 @@ fn setup_commands() -> CommandCollection {
     commands.add_command(Box::new(command_list::ListCommand::new()));
     commands.add_command(Box::new(command_install::InstallCommand::new()));
     commands.add_command(Box::new(command_wrap::WrapCommand::new()));
+    commands.add_command(Box::new(command_wrap_dir::WrapDirCommand::new()));
     commands.add_command(Box::new(command_fix::FixCommand::new()));
     commands.add_command(Box::new(command_drop::DropCommand::new()));
+    commands.add_command(Box::new(command_scan::ScanCommand::new()));
+    commands.add_command(Box::new(command_which::WhichCommand::new()));
+    commands.add_command(Box::new(command_show::ShowCommand::new()));
+    commands.add_command(Box::new(command_export::ExportCommand::new()));
+    commands.add_command(Box::new(command_import::ImportCommand::new()));
+    commands.add_command(Box::new(command_retarget::RetargetCommand::new()));
+    commands.add_command(Box::new(command_migrate::MigrateCommand::new()));
+    commands.add_command(Box::new(command_settings::SettingsCommand::new()));
+    commands.add_command(Box::new(command_stats::StatsCommand::new()));
     commands
 }
 
 fn main() -> Result<ExitCode, Box<dyn Error>> {
-    println!("{fg_g}{stl_i}WEXE executable wrapper - Configuration Utility{rst}.");
+    // Print the banner to stderr, so the output of commands can be redirected or piped
+    eprintln!("{fg_g}{stl_i}WEXE executable wrapper - Configuration Utility{rst}.");
 
     let commands = setup_commands();
-    let mut arguments = ArgumentsBuffer::new(std::env::args().skip(1).collect());
+    let args: Result<Vec<String>, OsString> =
+        std::env::args_os().skip(1).map(OsString::into_string).collect();
+    let args = match args {
+        Ok(args) => args,
+        Err(arg) => {
+            eprintln!("{fg_r}Argument is not valid Unicode: {fg_y}{}{rst}.", arg.to_string_lossy());
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+    let mut arguments = ArgumentsBuffer::new(args);
 
     match arguments.peek() {
         Some(name) => {
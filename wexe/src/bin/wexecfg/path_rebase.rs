@@ -0,0 +1,155 @@
+use wexe::config_model::{ConfigArgs, ConfigEnv, WexeAppConfig};
+
+/// Replaces a path prefix by another one in the paths found in application configurations:
+/// the target, PATH-like entries (including those to remove), environment variable
+/// values, `[args.paths]` mapping prefixes and arguments that look like paths (including
+/// the value part of `--option=value` arguments).
+pub struct PathRebase {
+    from: String,
+    to: String,
+}
+
+/// A single value changed by a [PathRebase]
+pub struct RebasedValue {
+    /// Where in the configuration the value was found (e.g. `env.set.JAVA_HOME`)
+    pub key: String,
+    pub old: String,
+    pub new: String,
+}
+
+fn is_separator(c: char) -> bool {
+    c == '/' || c == '\\'
+}
+
+impl PathRebase {
+    /// Create a rebase from prefix `from` to prefix `to` (trailing separators are ignored).
+    /// # Returns
+    /// The rebase, or a message if `from` is empty (or only separators, like `/`): such a
+    /// prefix would match every absolute path and every empty value.
+    pub fn new(from: &str, to: &str) -> Result<PathRebase, String> {
+        let trimmed_from = from.trim_end_matches(is_separator);
+        if trimmed_from.is_empty() {
+            return Err(format!(
+                "The --from prefix '{from}' is empty without its trailing separators, \
+                so it would match every path"
+            ));
+        }
+        Ok(PathRebase {
+            from: trimmed_from.to_string(),
+            to: to.trim_end_matches(is_separator).to_string(),
+        })
+    }
+
+    /// Returns the length of the prefix of `path` matching `self.from` (which must end
+    /// at a path separator or the end of `path`), if any.
+    fn match_prefix(&self, path: &str) -> Option<usize> {
+        let n = self.from.len();
+        if path.len() < n || !path.is_char_boundary(n) {
+            return None;
+        }
+        let (head, tail) = path.split_at(n);
+        #[cfg(windows)]
+        let same = head.eq_ignore_ascii_case(&self.from);
+        #[cfg(not(windows))]
+        let same = head == self.from;
+        if same && (tail.is_empty() || tail.starts_with(is_separator)) {
+            Some(n)
+        } else {
+            None
+        }
+    }
+
+    /// Rebase a single string if it starts with the 'from' prefix, or if it is of the form
+    /// `name=value` where the value starts with it. Returns None if nothing matches.
+    pub fn rebase_str(&self, text: &str) -> Option<String> {
+        if let Some(n) = self.match_prefix(text) {
+            return Some(format!("{}{}", self.to, &text[n..]));
+        }
+        if let Some((name, value)) = text.split_once('=')
+            && let Some(n) = self.match_prefix(value)
+        {
+            return Some(format!("{}={}{}", name, self.to, &value[n..]));
+        }
+        None
+    }
+
+    /// Rebase a single value, recording the change under `key`.
+    fn rebase_value(&self, key: String, value: &mut String, changes: &mut Vec<RebasedValue>) {
+        if let Some(new) = self.rebase_str(value) {
+            changes.push(RebasedValue {
+                key,
+                old: std::mem::replace(value, new.clone()),
+                new,
+            });
+        }
+    }
+
+    /// Rebase the named lists of a section, recording the changes under `key`.
+    fn rebase_lists(
+        &self,
+        key: &str,
+        lists: Vec<(&str, &mut Option<Vec<String>>)>,
+        changes: &mut Vec<RebasedValue>,
+    ) {
+        for (list_name, list) in lists {
+            for (index, value) in list.iter_mut().flatten().enumerate() {
+                self.rebase_value(format!("{key}.{list_name}[{index}]"), value, changes);
+            }
+        }
+    }
+
+    /// Rebase the arguments and environment changes of the base configuration or of
+    /// one of its `[os.*]` or `[profiles.*]` sections. `prefix` is prepended to the keys.
+    fn rebase_section(
+        &self,
+        prefix: &str,
+        args: Option<&mut ConfigArgs>,
+        env: Option<&mut ConfigEnv>,
+        changes: &mut Vec<RebasedValue>,
+    ) {
+        if let Some(args) = args {
+            let lists = vec![("prepend", &mut args.prepend), ("append", &mut args.append)];
+            self.rebase_lists(&format!("{prefix}args"), lists, changes);
+            let mappings = args.paths.iter_mut().flat_map(|p| p.map.iter_mut().flatten());
+            for (index, mapping) in mappings.enumerate() {
+                let key = format!("{prefix}args.paths.map[{index}]");
+                self.rebase_value(format!("{key}.from"), &mut mapping.from, changes);
+                self.rebase_value(format!("{key}.to"), &mut mapping.to, changes);
+            }
+        }
+        if let Some(env) = env {
+            for (name, value) in env.set.iter_mut().flatten() {
+                self.rebase_value(format!("{prefix}env.set.{name}"), value, changes);
+            }
+            for (name, ops) in env.pathlike.iter_mut().flatten() {
+                let lists = vec![
+                    ("prepend", &mut ops.prepend),
+                    ("append", &mut ops.append),
+                    ("remove", &mut ops.remove),
+                ];
+                self.rebase_lists(&format!("{prefix}env.pathlike.{name}"), lists, changes);
+            }
+        }
+    }
+
+    /// Rebase the paths in an application configuration model, including its `[os.*]`
+    /// and `[profiles.*]` sections. Returns the changed values, sorted by their key.
+    pub fn rebase_app_config(&self, app: &mut WexeAppConfig) -> Vec<RebasedValue> {
+        let mut changes = Vec::new();
+        self.rebase_value("target".into(), &mut app.target, &mut changes);
+        self.rebase_section("", app.args.as_mut(), app.env.as_mut(), &mut changes);
+        for (name, section) in app.os.iter_mut().flatten() {
+            let prefix = format!("os.{name}.");
+            if let Some(target) = section.target.as_mut() {
+                self.rebase_value(format!("{prefix}target"), target, &mut changes);
+            }
+            self.rebase_section(&prefix, section.args.as_mut(), section.env.as_mut(), &mut changes);
+        }
+        for (name, profile) in app.profiles.iter_mut().flatten() {
+            let prefix = format!("profiles.{name}.");
+            self.rebase_section(&prefix, profile.args.as_mut(), profile.env.as_mut(), &mut changes);
+        }
+        changes.sort_by(|a, b| a.key.cmp(&b.key));
+        changes
+    }
+}
@@ -1,13 +1,21 @@
 use std::collections::BTreeMap;
 use std::env::consts::EXE_SUFFIX;
+use std::ffi::OsStr;
+use std::fs;
 use std::fs::read_dir;
+use std::io;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 
 use chrono::DateTime;
 use chrono::offset::Utc;
+use same_file::is_same_file;
 
-use wexe::config_model::read_config_file;
-use wexe::config_model::{get_wexe_cfg_dir, is_valid_app_tag};
+use wexe::config_model::{ConfigError, read_config_file};
+use wexe::config_model::{
+    WEXE_SETTINGS_FILE_NAME, get_wexe_cfg_dir, is_valid_app_tag, read_wexe_settings_file,
+};
+use wexe::console_colors::*;
 
 pub struct WexeRepository {
     config_folder: PathBuf,
@@ -15,6 +23,42 @@ pub struct WexeRepository {
     wexe_exe_path: PathBuf,
     wexecfg_exe_path: PathBuf,
     entries: BTreeMap<String, WexeEntry>,
+    stub_strategy: StubKind,
+}
+
+/// The ways an application stub can refer to the wexe executable
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum StubKind {
+    /// A full copy of the wexe executable (the default)
+    Copy,
+    /// A hard link to the installed wexe executable
+    Hardlink,
+    /// A symbolic link to the installed wexe executable (Unix only)
+    Symlink,
+    /// A small `sh` script running the installed wexe executable with the tag as its
+    /// first argument (Unix only)
+    Script,
+}
+
+impl StubKind {
+    pub fn parse(text: &str) -> Option<StubKind> {
+        match text {
+            "copy" => Some(StubKind::Copy),
+            "hardlink" => Some(StubKind::Hardlink),
+            "symlink" => Some(StubKind::Symlink),
+            "script" => Some(StubKind::Script),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StubKind::Copy => "copy",
+            StubKind::Hardlink => "hardlink",
+            StubKind::Symlink => "symlink",
+            StubKind::Script => "script",
+        }
+    }
 }
 
 pub struct WexeEntry {
@@ -22,10 +66,10 @@ pub struct WexeEntry {
     stub_exe_path: PathBuf,
     cfg_path: PathBuf,
     target_exe_path: Option<PathBuf>, // None if configuration loading failed
-    load_error: Option<String>,       // None if configuration loading succeeded
+    load_error: Option<ConfigError>,  // None if configuration loading succeeded
 }
 
-pub fn get_file_stamp(file: &PathBuf) -> Option<DateTime<Utc>> {
+pub fn get_file_stamp(file: &Path) -> Option<DateTime<Utc>> {
     let meta_result = file.metadata();
     match meta_result {
         Ok(meta) => {
@@ -39,6 +83,54 @@ pub fn get_file_stamp(file: &PathBuf) -> Option<DateTime<Utc>> {
     }
 }
 
+/// Text identifying script stubs generated by wexecfg
+const SCRIPT_STUB_MARKER: &str = "wexe stub for";
+
+/// Returns true if the file starts with a `#!` (shebang) line.
+fn is_script_file(path: &Path) -> bool {
+    let mut header = [0u8; 2];
+    match fs::File::open(path) {
+        Ok(mut file) => file.read_exact(&mut header).is_ok() && &header == b"#!",
+        Err(_) => false,
+    }
+}
+
+/// Get the file name of the stub for an application (or of the wexe executables).
+pub fn stub_file_name(tag: &str) -> String {
+    tag.to_owned() + EXE_SUFFIX
+}
+
+/// Get the application tag from the file name of a stub (the inverse of [stub_file_name]).
+/// Returns None if the file name cannot be a stub name on this platform.
+pub fn stub_tag_from_file_name(file_name: &OsStr) -> Option<String> {
+    let file_name = file_name.to_str()?;
+    let tag = if EXE_SUFFIX.is_empty() {
+        file_name
+    } else {
+        let split = file_name.len().checked_sub(EXE_SUFFIX.len())?;
+        let (tag, suffix) = file_name.split_at_checked(split)?;
+        if !suffix.eq_ignore_ascii_case(EXE_SUFFIX) {
+            return None;
+        }
+        tag
+    };
+    if tag.is_empty() || tag.contains('.') {
+        None
+    } else {
+        Some(tag.to_string())
+    }
+}
+
+/// Get the application tag a stub is named after.
+fn stub_tag(stub_path: &Path) -> String {
+    stub_path.file_stem().unwrap_or_default().to_string_lossy().to_string()
+}
+
+/// Quote a string for use as a single word in a POSIX shell script.
+fn sh_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
 pub fn target_missing_or_older(source: &Path, target: &Path) -> bool {
     let meta_source = source.metadata();
     let meta_target = target.metadata();
@@ -53,6 +145,25 @@ pub fn target_missing_or_older(source: &Path, target: &Path) -> bool {
     }
 }
 
+/// Returns true if the file looks like something that can be executed directly.
+#[cfg(unix)]
+pub fn is_executable_file(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    match path.metadata() {
+        Ok(meta) => meta.is_file() && meta.permissions().mode() & 0o111 != 0,
+        Err(_) => false,
+    }
+}
+
+/// Returns true if the file looks like something that can be executed directly.
+#[cfg(not(unix))]
+pub fn is_executable_file(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(std::env::consts::EXE_EXTENSION))
+}
+
 impl WexeEntry {
     pub fn get_tag(&self) -> &str {
         &self.tag
@@ -62,7 +173,6 @@ impl WexeEntry {
         &self.stub_exe_path
     }
 
-    #[allow(dead_code)]
     pub fn get_cfg_path(&self) -> &PathBuf {
         &self.cfg_path
     }
@@ -75,60 +185,81 @@ impl WexeEntry {
         self.target_exe_path.is_some() && self.target_exe_path.as_ref().unwrap().exists()
     }
 
-    pub fn get_load_error(&self) -> &Option<String> {
+    pub fn get_load_error(&self) -> &Option<ConfigError> {
         &self.load_error
     }
 }
 
 impl WexeRepository {
     pub fn new() -> WexeRepository {
-        let config_folder = get_wexe_cfg_dir();
+        WexeRepository::open(get_wexe_cfg_dir())
+    }
+
+    /// Load the repository in the given configuration folder (instead of the default one).
+    pub fn open(config_folder: PathBuf) -> WexeRepository {
         // let bin_folder = get_wexe_cfg_bin_dir();
         let mut entries = BTreeMap::new();
         for direntry in read_dir(&config_folder).unwrap() {
             let entry = direntry.unwrap();
             let path = entry.path();
-            if path.is_file() {
-                if let Some(extension) = path.extension() {
-                    if extension == "toml" {
-                        let tag = path.file_stem().unwrap().to_str().unwrap().to_string();
-                        if is_valid_app_tag(&tag) {
-                            let cfg_path = path;
-                            let stub_exe_path = config_folder.join(tag.clone() + EXE_SUFFIX);
-                            let wexeapp = read_config_file(cfg_path.clone());
-                            let target_exe_path;
-                            let load_error;
-                            match wexeapp {
-                                Ok(app) => {
-                                    target_exe_path = Some(PathBuf::from(app.target));
-                                    load_error = None;
-                                }
-                                Err(e) => {
-                                    target_exe_path = None;
-                                    load_error = Some(e.to_string());
-                                }
-                            }
-                            entries.insert(
-                                tag.clone(),
-                                WexeEntry {
-                                    tag,
-                                    stub_exe_path,
-                                    cfg_path,
-                                    target_exe_path,
-                                    load_error,
-                                },
-                            );
+            if path.is_file()
+                && let Some(extension) = path.extension()
+                && extension == "toml"
+            {
+                // File names that are not valid Unicode are not valid tags either
+                let tag = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+                let tag = tag.to_string();
+                if is_valid_app_tag(&tag) {
+                    let cfg_path = path;
+                    let stub_exe_path = config_folder.join(stub_file_name(&tag));
+                    let wexeapp = read_config_file(cfg_path.clone());
+                    let target_exe_path;
+                    let load_error;
+                    match wexeapp {
+                        Ok(app) => {
+                            target_exe_path = Some(PathBuf::from(app.target));
+                            load_error = None;
+                        }
+                        Err(e) => {
+                            target_exe_path = None;
+                            load_error = Some(e);
                         }
                     }
+                    entries.insert(
+                        tag.clone(),
+                        WexeEntry {
+                            tag,
+                            stub_exe_path,
+                            cfg_path,
+                            target_exe_path,
+                            load_error,
+                        },
+                    );
                 }
             }
         }
+        let stub_strategy = match read_wexe_settings_file(&config_folder.join(WEXE_SETTINGS_FILE_NAME)) {
+            Ok(settings) => match settings.stub_strategy.as_deref() {
+                None => StubKind::Copy,
+                Some(name) => StubKind::parse(name).unwrap_or_else(|| {
+                    eprintln!(
+                        "{fg_o}Unknown stub strategy {fg_y}{name}{fg_o} in the settings; using {fg_y}copy{rst}."
+                    );
+                    StubKind::Copy
+                }),
+            },
+            Err(e) => {
+                eprintln!("{fg_o}Ignoring broken settings file: {fg_r}{e}{rst}.");
+                StubKind::Copy
+            }
+        };
         WexeRepository {
-            config_folder,
             // bin_folder,
-            wexe_exe_path: get_wexe_cfg_dir().join("wexe".to_string() + EXE_SUFFIX),
-            wexecfg_exe_path: get_wexe_cfg_dir().join("wexecfg".to_string() + EXE_SUFFIX),
+            wexe_exe_path: config_folder.join(stub_file_name("wexe")),
+            wexecfg_exe_path: config_folder.join(stub_file_name("wexecfg")),
+            config_folder,
             entries,
+            stub_strategy,
         }
     }
 
@@ -161,7 +292,135 @@ impl WexeRepository {
     /// Create the path to the stub executable for the given tag.
     /// (the resulting path may or may not exist as a file)
     pub fn get_stub_path(&self, tag: &str) -> PathBuf {
-        self.config_folder.join(tag.to_owned() + EXE_SUFFIX)
+        self.config_folder.join(stub_file_name(tag))
+    }
+
+    /// Returns true if the file looks like an application stub. On Windows that is any
+    /// '.exe' file; elsewhere stubs have no extension, so the file must also be executable,
+    /// or be a link to or a script for the installed wexe executable.
+    pub fn is_stub_file(&self, path: &Path) -> bool {
+        if cfg!(windows) {
+            return is_executable_file(path);
+        }
+        match self.get_stub_kind(path) {
+            Some(StubKind::Symlink) => fs::read_link(path).is_ok_and(|link| link == self.wexe_exe_path),
+            Some(StubKind::Hardlink) => true,
+            Some(StubKind::Script) => {
+                fs::read_to_string(path).is_ok_and(|text| text.contains(SCRIPT_STUB_MARKER))
+            }
+            Some(StubKind::Copy) => is_executable_file(path),
+            None => false,
+        }
+    }
+
+    /// Find the files in the configuration folder that look like application stubs
+    /// (see [WexeRepository::is_stub_file]), whether or not they have a configuration.
+    /// Returns the tags they are named after (not lowercased) and their paths.
+    /// The installed wexe and wexecfg executables are not included.
+    pub fn find_stub_files(&self) -> Vec<(String, PathBuf)> {
+        let mut stubs = Vec::new();
+        let Ok(dir) = read_dir(&self.config_folder) else {
+            return stubs;
+        };
+        for entry in dir.flatten() {
+            let path = entry.path();
+            let Some(tag) = path.file_name().and_then(stub_tag_from_file_name) else {
+                continue;
+            };
+            let lower_tag = tag.to_lowercase();
+            if lower_tag == "wexe" || lower_tag == "wexecfg" || !self.is_stub_file(&path) {
+                continue;
+            }
+            stubs.push((tag, path));
+        }
+        stubs.sort();
+        stubs
+    }
+
+    /// Get the way new stubs are created in this repository (the "stub_strategy" setting).
+    pub fn get_stub_strategy(&self) -> StubKind {
+        self.stub_strategy
+    }
+
+    /// Determine how an existing stub refers to the wexe executable.
+    /// Returns None if the stub does not exist.
+    pub fn get_stub_kind(&self, stub_path: &Path) -> Option<StubKind> {
+        let meta = stub_path.symlink_metadata().ok()?;
+        if meta.file_type().is_symlink() {
+            Some(StubKind::Symlink)
+        } else if is_same_file(stub_path, &self.wexe_exe_path).unwrap_or(false) {
+            Some(StubKind::Hardlink)
+        } else if is_script_file(stub_path) {
+            Some(StubKind::Script)
+        } else {
+            Some(StubKind::Copy)
+        }
+    }
+
+    /// Determine the kind a stub should have: script stubs are chosen explicitly (with
+    /// `/wrap --stub-kind script`) and are kept as scripts, all other stubs follow the
+    /// stub strategy of the repository.
+    pub fn get_desired_stub_kind(&self, stub_path: &Path) -> StubKind {
+        match self.get_stub_kind(stub_path) {
+            Some(StubKind::Script) => StubKind::Script,
+            _ => self.stub_strategy,
+        }
+    }
+
+    /// Get the content of the script stub for an application.
+    pub fn get_script_stub_text(&self, tag: &str) -> String {
+        format!(
+            "#!/bin/sh\n# {SCRIPT_STUB_MARKER} '{tag}'. Generated by wexecfg; use 'wexecfg /fix' to update.\n\
+            exec {} {tag} \"$@\"\n",
+            sh_quote(&self.wexe_exe_path.to_string_lossy())
+        )
+    }
+
+    /// Returns true if the stub exists, has the kind it should have (see
+    /// [WexeRepository::get_desired_stub_kind]) and runs the currently installed wexe
+    /// executable.
+    pub fn is_stub_current(&self, stub_path: &Path) -> bool {
+        let desired_kind = self.get_desired_stub_kind(stub_path);
+        match self.get_stub_kind(stub_path) {
+            Some(kind) if kind == desired_kind => match kind {
+                StubKind::Copy => !target_missing_or_older(&self.wexe_exe_path, stub_path),
+                StubKind::Hardlink => true,
+                StubKind::Symlink => fs::read_link(stub_path)
+                    .is_ok_and(|link| link == self.wexe_exe_path),
+                StubKind::Script => fs::read_to_string(stub_path)
+                    .is_ok_and(|text| text == self.get_script_stub_text(&stub_tag(stub_path))),
+            },
+            _ => false,
+        }
+    }
+
+    /// Create (or replace) a stub of the given kind.
+    pub fn create_stub(&self, stub_path: &Path, kind: StubKind) -> io::Result<()> {
+        if stub_path.symlink_metadata().is_ok() {
+            fs::remove_file(stub_path)?;
+        }
+        match kind {
+            StubKind::Copy => fs::copy(&self.wexe_exe_path, stub_path).map(|_| ()),
+            StubKind::Hardlink => fs::hard_link(&self.wexe_exe_path, stub_path),
+            #[cfg(unix)]
+            StubKind::Symlink => std::os::unix::fs::symlink(&self.wexe_exe_path, stub_path),
+            #[cfg(not(unix))]
+            StubKind::Symlink => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Symbolic link stubs are only supported on Unix",
+            )),
+            #[cfg(unix)]
+            StubKind::Script => {
+                use std::os::unix::fs::PermissionsExt;
+                fs::write(stub_path, self.get_script_stub_text(&stub_tag(stub_path)))?;
+                fs::set_permissions(stub_path, fs::Permissions::from_mode(0o755))
+            }
+            #[cfg(not(unix))]
+            StubKind::Script => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Script stubs are only supported on Unix",
+            )),
+        }
     }
 
     /// Create the path to the configuration file for the given tag.
@@ -170,3 +429,94 @@ impl WexeRepository {
         self.config_folder.join(tag.to_owned() + ".toml")
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// Create an empty repository folder with fake wexe and wexecfg executables.
+    fn make_repository_folder(name: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("wexecfg-test-{}-{name}", std::process::id()));
+        if folder.exists() {
+            fs::remove_dir_all(&folder).unwrap();
+        }
+        fs::create_dir_all(&folder).unwrap();
+        for exe in ["wexe", "wexecfg"] {
+            write_executable(&folder.join(exe), "fake executable");
+        }
+        folder
+    }
+
+    fn write_executable(path: &Path, content: &str) {
+        fs::write(path, content).unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn stub_names_have_no_extension() {
+        assert_eq!(stub_file_name("tool"), "tool");
+        assert_eq!(stub_tag_from_file_name(OsStr::new("tool")), Some("tool".to_string()));
+        assert_eq!(stub_tag_from_file_name(OsStr::new("tool.exe")), None);
+        assert_eq!(stub_tag_from_file_name(OsStr::new("tool.toml")), None);
+        assert_eq!(stub_tag_from_file_name(OsStr::new("")), None);
+    }
+
+    #[test]
+    fn stub_path_matches_entry_stub_path() {
+        let folder = make_repository_folder("stub-path");
+        fs::write(folder.join("tool.toml"), "target = '/bin/sh'\n").unwrap();
+        let repo = WexeRepository::open(folder.clone());
+        let entry = repo.find_entry("tool").unwrap();
+        assert_eq!(repo.get_stub_path("tool"), folder.join("tool"));
+        assert_eq!(entry.get_stub_exe_path(), &folder.join("tool"));
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn stub_files_exclude_wexe_and_non_stubs() {
+        let folder = make_repository_folder("find-stubs");
+        fs::write(folder.join("tool.toml"), "target = '/bin/sh'\n").unwrap();
+        fs::write(folder.join("notes"), "not executable").unwrap();
+        fs::write(folder.join("wexe.settings.toml"), "").unwrap();
+        write_executable(&folder.join("other.exe"), "not a stub name on unix");
+        let repo = WexeRepository::open(folder.clone());
+        repo.create_stub(&repo.get_stub_path("tool"), StubKind::Copy).unwrap();
+        repo.create_stub(&repo.get_stub_path("orphan"), StubKind::Symlink).unwrap();
+        repo.create_stub(&repo.get_stub_path("shim"), StubKind::Script).unwrap();
+        let tags: Vec<String> = repo.find_stub_files().into_iter().map(|(tag, _)| tag).collect();
+        assert_eq!(tags, vec!["orphan", "shim", "tool"]);
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn stub_kinds_are_detected() {
+        let folder = make_repository_folder("stub-kinds");
+        let repo = WexeRepository::open(folder.clone());
+        for kind in [StubKind::Copy, StubKind::Hardlink, StubKind::Symlink, StubKind::Script] {
+            let stub_path = repo.get_stub_path(kind.name());
+            repo.create_stub(&stub_path, kind).unwrap();
+            assert_eq!(repo.get_stub_kind(&stub_path), Some(kind));
+            assert!(repo.is_stub_file(&stub_path));
+        }
+        assert_eq!(repo.get_stub_kind(&repo.get_stub_path("missing")), None);
+        // A foreign link is not a stub
+        std::os::unix::fs::symlink("/bin/sh", folder.join("foreign")).unwrap();
+        assert!(!repo.is_stub_file(&folder.join("foreign")));
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn script_stubs_stay_scripts() {
+        let folder = make_repository_folder("script-stubs");
+        let repo = WexeRepository::open(folder.clone());
+        let stub_path = repo.get_stub_path("shim");
+        repo.create_stub(&stub_path, StubKind::Script).unwrap();
+        assert_eq!(repo.get_desired_stub_kind(&stub_path), StubKind::Script);
+        assert!(repo.is_stub_current(&stub_path));
+        fs::write(&stub_path, "#!/bin/sh\n").unwrap();
+        assert!(!repo.is_stub_current(&stub_path));
+        assert_eq!(repo.get_desired_stub_kind(&repo.get_stub_path("other")), StubKind::Copy);
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
@@ -1,15 +1,25 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::error::Error;
+use std::ffi::OsString;
+use std::fmt;
 use std::fs;
+use std::io;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use toml;
+use toml_edit::{Array, ArrayOfTables, DocumentMut, Item, Table, TableLike, Value};
 
+use crate::args_rewrite::RewriteRule;
 use crate::console_colors::*;
+use crate::launch_retry::RetryPolicy;
+use crate::launch_timeout::parse_duration;
 
 pub struct WexeConfigFolders {
     pub wexe_cfg_dir: PathBuf,
@@ -63,13 +73,23 @@ lazy_static! {
     static ref APP_TAG_REGEX: Regex = Regex::new(r"^[a-z][a-z0-9]*([-_][a-z0-9]+)*$").unwrap();
 }
 
+/// Set by [enable_wexe_dbg], overriding the WEXE_DEBUG environment variable
+static WEXE_DEBUG_ENABLED: AtomicBool = AtomicBool::new(false);
+
 /// Returns true if the wexe debug flag is set.
 /// This is set if the environment variable WEXE_DEBUG is set to "1",
-/// or if the program is built in debug mode and WEXE_DEBUG is not set.
+/// or if the program is built in debug mode and WEXE_DEBUG is not set,
+/// or if [enable_wexe_dbg] was called.
 /// # Returns
 /// True if the wexe debug flag is set.
 pub fn wexe_dbg() -> bool {
-    *WEXE_DEBUG
+    WEXE_DEBUG_ENABLED.load(Ordering::Relaxed) || *WEXE_DEBUG
+}
+
+/// Turn on the wexe debug flag for the rest of this process, whatever the value of
+/// the WEXE_DEBUG environment variable.
+pub fn enable_wexe_dbg() {
+    WEXE_DEBUG_ENABLED.store(true, Ordering::Relaxed);
 }
 
 /// Returns true if the given tag is a valid wexe application tag.
@@ -101,34 +121,285 @@ pub fn get_wexe_cfg_dir() -> PathBuf {
 //     WEXE_CFG_FOLDERS.wexe_bin_dir.clone()
 // }
 
-/// Get the path to a configuration file for a given tag, or None if no such file exists.
+/// Get the path to the repository-wide settings file in the wexe configuration directory.
+/// Its name is not a valid application tag, so it is never mistaken for an application
+/// configuration. (the resulting path may or may not exist as a file)
+pub fn get_wexe_settings_file() -> PathBuf {
+    get_wexe_cfg_dir().join(WEXE_SETTINGS_FILE_NAME)
+}
+
+/// The name of the repository-wide settings file in the wexe configuration directory
+pub const WEXE_SETTINGS_FILE_NAME: &str = "wexe.settings.toml";
+
+/// Repository-wide settings, shared by all applications. All settings are optional;
+/// a missing settings file is equivalent to an empty one.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WexeSettings {
+    /// How application stubs are created: "copy" (the default), "hardlink", "symlink" or "script"
+    pub stub_strategy: Option<String>,
+    /// The launch log file for the applications that do not set their own `log`
+    pub log: Option<String>,
+    /// The size (in bytes) beyond which launch log files are rotated (0: never)
+    pub log_max_size: Option<u64>,
+    /// The number of rotated launch log files kept
+    pub log_keep: Option<usize>,
+    /// Whether launches are counted in the usage statistics file (default: not)
+    pub stats: Option<bool>,
+}
+
+/// Read the repository-wide settings file, returning default settings if it does not exist.
+pub fn read_wexe_settings() -> Result<WexeSettings, Box<dyn Error>> {
+    read_wexe_settings_file(&get_wexe_settings_file())
+}
+
+/// Read a settings file, returning default settings if it does not exist.
+pub fn read_wexe_settings_file(settings_file: &Path) -> Result<WexeSettings, Box<dyn Error>> {
+    if !settings_file.exists() {
+        return Ok(WexeSettings::default());
+    }
+    let text = std::fs::read_to_string(settings_file)?;
+    let settings: WexeSettings = toml::from_str(&text)?;
+    Ok(settings)
+}
+
+/// Errors that can occur when locating or loading an application configuration
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The application tag is not valid
+    InvalidTag(String),
+    /// There is no configuration file for the application tag
+    NotFound(String),
+    /// The configuration file exists but could not be read
+    Unreadable { path: PathBuf, source: io::Error },
+    /// The configuration file is not valid TOML, or does not match the configuration model
+    Parse {
+        path: PathBuf,
+        message: String,
+        /// The byte range in the file the error refers to, if known
+        span: Option<Range<usize>>,
+        /// The line and column (both 1-based) where the span starts, if known
+        line_col: Option<(usize, usize)>,
+    },
+    /// The target executable path in the configuration is not absolute
+    NonAbsoluteTarget { path: PathBuf, target: String },
+    /// The configuration file was written for a newer configuration schema than this
+    /// version of wexe supports
+    UnsupportedSchema { path: PathBuf, schema: i64 },
+    /// The selected profile (or the default profile) does not exist in the configuration
+    UnknownProfile { path: PathBuf, profile: String },
+    /// An `[[args.rewrite]]` rule is invalid. `rule` is its 1-based number, counting
+    /// the rules of the selected `[os.*]` section and profile after the others.
+    InvalidRewriteRule {
+        path: PathBuf,
+        rule: usize,
+        message: String,
+    },
+    /// A regular expression in the `match` list of the `[args.paths]` section is invalid
+    InvalidPathPattern {
+        path: PathBuf,
+        pattern: String,
+        message: String,
+    },
+    /// A glob pattern in the `env.keep` list is invalid
+    InvalidKeepPattern {
+        path: PathBuf,
+        pattern: String,
+        message: String,
+    },
+    /// The `timeout` or `kill_after` key is not a valid duration
+    InvalidDuration {
+        path: PathBuf,
+        key: String,
+        value: String,
+    },
+    /// The `[retry]` section is invalid
+    InvalidRetry { path: PathBuf, message: String },
+    /// A configuration file included by `path` could not be loaded. Not raised yet:
+    /// reserved for when `include` is implemented (see [WexeAppConfig::target]).
+    Include {
+        path: PathBuf,
+        include: PathBuf,
+        source: Box<ConfigError>,
+    },
+    /// The location of the running executable could not be determined
+    NoExecutablePath(io::Error),
+    /// The wexecfg executable is not installed next to wexe
+    WexecfgNotFound(PathBuf),
+    /// A path that would become the target of an application is not valid Unicode
+    NonUnicodePath(PathBuf),
+}
+
+impl ConfigError {
+    /// Create a [ConfigError::Parse] for a TOML error in the file `path` with content `text`.
+    pub fn from_toml(path: &Path, text: &str, error: toml::de::Error) -> ConfigError {
+        let span = error.span();
+        let line_col = span.as_ref().map(|span| {
+            let before = &text[..span.start.min(text.len())];
+            let line = before.matches('\n').count() + 1;
+            let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+            (line, column)
+        });
+        ConfigError::Parse {
+            path: path.to_path_buf(),
+            message: error.message().trim_end().to_string(),
+            span,
+            line_col,
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::InvalidTag(tag) => write!(f, "Invalid app tag '{tag}'."),
+            ConfigError::NotFound(tag) => write!(f, "No configuration file for '{tag}'."),
+            ConfigError::Unreadable { path, source } => {
+                write!(f, "Cannot read {}: {source}", path.to_string_lossy())
+            }
+            ConfigError::Parse {
+                path,
+                message,
+                line_col,
+                ..
+            } => match line_col {
+                Some((line, column)) => write!(
+                    f,
+                    "{}, line {line}, column {column}: {message}",
+                    path.to_string_lossy()
+                ),
+                None => write!(f, "{}: {message}", path.to_string_lossy()),
+            },
+            ConfigError::NonAbsoluteTarget { target, .. } => {
+                write!(f, "Target executable path must be absolute: {target}")
+            }
+            ConfigError::UnsupportedSchema { path, schema } => write!(
+                f,
+                "{} uses configuration schema {schema}, but this version of wexe only supports \
+                schema {CONFIG_SCHEMA} and older. Upgrade wexe to use it",
+                path.to_string_lossy()
+            ),
+            ConfigError::UnknownProfile { path, profile } => {
+                write!(f, "Unknown profile '{profile}' in {}", path.to_string_lossy())
+            }
+            ConfigError::InvalidRewriteRule {
+                path,
+                rule,
+                message,
+            } => write!(
+                f,
+                "{}: argument rewrite rule {rule} is invalid: {message}",
+                path.to_string_lossy()
+            ),
+            ConfigError::InvalidPathPattern {
+                path,
+                pattern,
+                message,
+            } => write!(
+                f,
+                "{}: invalid path argument pattern '{pattern}': {message}",
+                path.to_string_lossy()
+            ),
+            ConfigError::InvalidKeepPattern {
+                path,
+                pattern,
+                message,
+            } => write!(
+                f,
+                "{}: invalid env.keep pattern '{pattern}': {message}",
+                path.to_string_lossy()
+            ),
+            ConfigError::InvalidDuration { path, key, value } => write!(
+                f,
+                "{}: invalid duration for {key}: '{value}' (expecting a number of seconds, or \
+                a number with unit ms, s, m, h or d)",
+                path.to_string_lossy()
+            ),
+            ConfigError::InvalidRetry { path, message } => {
+                write!(f, "{}: invalid [retry] section: {message}", path.to_string_lossy())
+            }
+            ConfigError::Include {
+                path,
+                include,
+                source,
+            } => write!(
+                f,
+                "{}: cannot include {}: {source}",
+                path.to_string_lossy(),
+                include.to_string_lossy()
+            ),
+            ConfigError::NoExecutablePath(source) => {
+                write!(f, "Cannot determine the location of the executable: {source}")
+            }
+            ConfigError::WexecfgNotFound(path) => {
+                write!(f, "Could not find the wexecfg executable file: {}", path.to_string_lossy())
+            }
+            ConfigError::NonUnicodePath(path) => {
+                write!(f, "Path is not valid Unicode: {}", path.to_string_lossy())
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::Unreadable { source, .. } => Some(source),
+            ConfigError::Include { source, .. } => Some(source.as_ref()),
+            ConfigError::NoExecutablePath(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Get the path to the configuration file for a given tag.
 /// # Arguments
 /// * `tag` - The tag to use to find the configuration file.
 /// # Returns
-/// The path to the configuration file, or None if no such file exists.
-pub fn get_config_file(tag: String) -> Option<PathBuf> {
+/// The path to the configuration file, or [ConfigError::NotFound] if no such file exists.
+pub fn get_config_file(tag: String) -> Result<PathBuf, ConfigError> {
     if !is_valid_app_tag(&tag) {
-        panic!("Invalid application tag: {}", tag);
-    }
-    let exe = env::current_exe().unwrap();
-    let folder = exe.parent().unwrap();
-    let cfg_file = folder.join(tag.clone() + ".toml");
-    if cfg_file.exists() {
-        return Some(cfg_file);
+        return Err(ConfigError::InvalidTag(tag));
     }
-    let wexe_cfg_dir = get_wexe_cfg_dir();
-    let cfg_file = wexe_cfg_dir.join(tag.clone() + ".toml");
-    if cfg_file.exists() {
-        return Some(cfg_file);
+    let exe = env::current_exe().map_err(ConfigError::NoExecutablePath)?;
+    let folder = exe.parent().unwrap_or(Path::new(""));
+    config_file_search_path(&tag, folder)
+        .into_iter()
+        .find(|cfg_file| cfg_file.exists())
+        .ok_or(ConfigError::NotFound(tag))
+}
+
+/// Get the candidate paths for the configuration file of a given tag, in order of
+/// precedence, for a stub executable located in `stub_folder`. The first one that
+/// exists is the one used. A configuration file next to the stub overrides the
+/// one in the wexe configuration directory.
+/// # Arguments
+/// * `tag` - The tag to find the configuration file for.
+/// * `stub_folder` - The folder containing the stub executable.
+/// # Returns
+/// The candidate configuration file paths (which may or may not exist).
+pub fn config_file_search_path(tag: &str, stub_folder: &Path) -> Vec<PathBuf> {
+    let file_name = tag.to_string() + ".toml";
+    let mut candidates = vec![stub_folder.join(&file_name)];
+    let wexe_cfg_file = get_wexe_cfg_dir().join(&file_name);
+    if !candidates.contains(&wexe_cfg_file) {
+        candidates.push(wexe_cfg_file);
     }
-    None
+    candidates
 }
 
+/// The configuration schema version written by this version of wexe. Configuration
+/// files declare the schema they were written for in their `schema` key; files with a
+/// newer schema are rejected, older ones can be upgraded with `wexecfg /migrate`.
+pub const CONFIG_SCHEMA: i64 = 1;
+
 /// Top level configuration file model. This models the actual content of the
 /// configuration file, including some optional parts.
 /// See the [WexeApp] struct for the disambiguated model.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WexeAppConfig {
+    /// The configuration schema version the file was written for. Files without it
+    /// predate schema versioning (schema 0).
+    pub schema: Option<i64>,
     /// The target executable to run
     // ((currently required; may be optional in the future once we get 'include' implemented))
     pub target: String,
@@ -136,29 +407,216 @@ pub struct WexeAppConfig {
     pub env: Option<ConfigEnv>,
     /// Arguments to prepend and append to the command line
     pub args: Option<ConfigArgs>,
+    /// Overrides for specific operating systems (the `[os.linux]`, `[os.macos]`,
+    /// `[os.windows]`, ... sections), indexed by operating system name
+    pub os: Option<BTreeMap<String, ConfigOverride>>,
+    /// The prefix of the reserved arguments controlling wexe itself when launching the
+    /// application (see [crate::launcher::LaunchFlags]). Defaults to
+    /// [DEFAULT_WEXE_FLAG_PREFIX]; an empty prefix disables them.
+    pub wexe_flag_prefix: Option<String>,
+    /// The launch log file of the application, overriding the `log` repository setting
+    /// (see [crate::launch_log::LaunchLog])
+    pub log: Option<String>,
+    /// How long the target may run before it is stopped, as in `"30m"` (see
+    /// [crate::launch_timeout::LaunchTimeout])
+    pub timeout: Option<String>,
+    /// The grace period between asking the target to terminate and killing it, once the
+    /// timeout has expired
+    pub kill_after: Option<String>,
+    /// When and how often the target is started again if it fails (the `[retry]` section)
+    pub retry: Option<ConfigRetry>,
+    /// The profile used when no other profile is selected
+    pub default_profile: Option<String>,
+    /// Named profiles (the `[profiles.<name>]` sections) that can be selected at launch time
+    pub profiles: Option<BTreeMap<String, ConfigProfile>>,
 }
 
-/// Optional lists of elements to prepend or append to some existing string list
-/// (be they the arguments list or a PATH-like environment variable).
-#[derive(Debug, Serialize, Deserialize)]
+/// Models a `[profiles.<name>]` section of the configuration file: arguments and
+/// environment changes applied when the profile is selected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigProfile {
+    /// Extends the environment variable related sections
+    pub env: Option<ConfigEnv>,
+    /// Extends the arguments to prepend and append
+    pub args: Option<ConfigArgs>,
+}
+
+/// Models an `[os.<name>]` section of the configuration file: overrides applied on top
+/// of the rest of the configuration when running on operating system `<name>` (named
+/// as in [std::env::consts::OS]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigOverride {
+    /// Replaces the target executable
+    pub target: Option<String>,
+    /// Extends the environment variable related sections
+    pub env: Option<ConfigEnv>,
+    /// Extends the arguments to prepend and append
+    pub args: Option<ConfigArgs>,
+}
+
+impl WexeAppConfig {
+    /// Get the names of the `[os.*]` sections, in order, each with a flag telling if it
+    /// applies on operating system `os`.
+    pub fn os_sections(&self, os: &str) -> Vec<(String, bool)> {
+        self.os
+            .iter()
+            .flatten()
+            .map(|(name, _)| (name.clone(), name == os))
+            .collect()
+    }
+
+    /// Get the names of the profiles, in order.
+    pub fn profile_names(&self) -> Vec<String> {
+        self.profiles.iter().flatten().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// Select the profile to use. A `requested` profile (for instance from a `+profile`
+    /// argument) must exist. Otherwise the profile named by the [WEXE_PROFILE_VAR]
+    /// environment variable is used if this configuration has it (the variable may be
+    /// meant for other applications), and otherwise the `default_profile`, which must
+    /// exist if given.
+    /// # Returns
+    /// The name of the selected profile, if any, or the name of a missing profile as error.
+    pub fn select_profile(&self, requested: Option<&str>) -> Result<Option<String>, String> {
+        let exists = |name: &str| self.profiles.as_ref().is_some_and(|p| p.contains_key(name));
+        if let Some(name) = requested {
+            return if exists(name) { Ok(Some(name.to_string())) } else { Err(name.to_string()) };
+        }
+        if let Ok(name) = env::var(WEXE_PROFILE_VAR)
+            && exists(&name)
+        {
+            return Ok(Some(name));
+        }
+        match &self.default_profile {
+            Some(name) if exists(name) => Ok(Some(name.clone())),
+            Some(name) => Err(name.clone()),
+            None => Ok(None),
+        }
+    }
+}
+
+/// The default prefix of the reserved arguments controlling wexe itself
+pub const DEFAULT_WEXE_FLAG_PREFIX: &str = "--wexe-";
+
+/// The environment variable selecting the profile of the applications that have it
+pub const WEXE_PROFILE_VAR: &str = "WEXE_PROFILE";
+
+/// Models an `args` section: optional lists of arguments to prepend or append to the
+/// given arguments, and how to rewrite the given arguments.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ConfigArgs {
     pub prepend: Option<Vec<String>>,
     pub append: Option<Vec<String>>,
+    /// Rules rewriting the arguments given on the command line (the `[[args.rewrite]]`
+    /// tables)
+    pub rewrite: Option<Vec<ConfigRewriteRule>>,
+    /// Translation of path arguments given on the command line (the `[args.paths]`
+    /// table)
+    pub paths: Option<ConfigArgPaths>,
+}
+
+/// Models an `env.pathlike.<NAME>` section: optional lists of elements to prepend or
+/// append to a PATH-like environment variable, and how to clean up its elements.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigPathlike {
+    pub prepend: Option<Vec<String>>,
+    pub append: Option<Vec<String>>,
+    /// Keep only the first occurrence of each element
+    pub dedupe: Option<bool>,
+    /// Skip elements naming folders that do not exist
+    pub drop_missing: Option<bool>,
+    /// Normalize the elements: remove trailing separators, `.` and resolvable `..`
+    pub normalize: Option<bool>,
+    /// Elements to remove from the inherited value of the variable
+    pub remove: Option<Vec<String>>,
+}
+
+/// Models the `[args.paths]` section: translating the arguments given on the command
+/// line that are paths (after the `[[args.rewrite]]` rules are applied). An argument
+/// is a path if it matches one of the regular expressions in `match`, or (with
+/// `existing = true`) if it names an existing file or folder.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigArgPaths {
+    /// Make relative paths absolute, relative to the current folder (default: true)
+    pub absolute: Option<bool>,
+    /// Consider arguments naming an existing file or folder as paths (default: false)
+    pub existing: Option<bool>,
+    /// Regular expressions for arguments to consider as paths, even if they do not exist
+    #[serde(rename = "match")]
+    pub patterns: Option<Vec<String>>,
+    /// Prefix mappings applied to the (absolute) paths; the first matching one is used
+    pub map: Option<Vec<ConfigPathMapping>>,
+}
+
+/// Models the `[retry]` section of the configuration file: the policy for starting the
+/// target again when it fails (see [crate::launch_retry::RetryPolicy]).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigRetry {
+    /// The maximum number of times the target is started, including the first
+    pub attempts: Option<u32>,
+    /// How the delay between attempts grows: "fixed" (the default) or "exponential"
+    pub backoff: Option<String>,
+    /// The delay before the first retry (default 1s)
+    pub delay: Option<String>,
+    /// The exit codes that cause a retry (default: any non-zero exit code)
+    pub on_exit_codes: Option<Vec<i32>>,
+    /// Whether the target being terminated by a signal causes a retry (default: false)
+    pub on_signal: Option<bool>,
+    /// Whether to retry even if standard input is a pipe, which cannot be replayed for
+    /// the next attempt (default: false)
+    pub piped_stdin: Option<bool>,
+}
+
+/// A prefix mapping in the `[args.paths]` section, for instance from `/mnt/c/` to `C:\`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigPathMapping {
+    /// The prefix to replace
+    pub from: String,
+    /// The replacement for the prefix
+    pub to: String,
+    /// If given, the separator replacing `/` and `\` in the rest of the path
+    pub separator: Option<String>,
+}
+
+/// Models an `[[args.rewrite]]` rule, rewriting the arguments given on the command line
+/// (not the ones the configuration prepends or appends). Rules are applied in order.
+/// A rule either
+/// * replaces the matches of the regular expression `match` in each argument by
+///   `replace` (which can refer to capture groups, as in `$1` or `${name}`), or
+/// * drops the arguments matching `match` (with `drop = true`), or
+/// * inserts the argument `insert` before the given arguments, unless one of them
+///   matches the regular expression `unless` (by default: unless `insert` was given).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigRewriteRule {
+    #[serde(rename = "match")]
+    pub pattern: Option<String>,
+    pub replace: Option<String>,
+    pub drop: Option<bool>,
+    pub insert: Option<String>,
+    pub unless: Option<String>,
 }
 
 /// Models the "env" section of the configuration file
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConfigEnv {
     /// Environment variables to set, override, or delete.
     /// An environment variable is deleted if its value is an empty string.
     pub set: Option<HashMap<String, String>>,
     /// Sections for mofifying PATH-like environment variables.
-    pub pathlike: Option<HashMap<String, ConfigArgs>>,
+    pub pathlike: Option<HashMap<String, ConfigPathlike>>,
+    /// Start the target with an empty environment (except for the `keep` variables),
+    /// before `set` and `pathlike` are applied.
+    pub clear: Option<bool>,
+    /// Glob patterns (like `LANG*`) for the environment variables kept when `clear`
+    /// is true.
+    pub keep: Option<Vec<String>>,
 }
 
 /// Lists of elements to prepend or append to some existing string list
 /// (be they the arguments list or a PATH-like environment variable).
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
 pub struct ListOps {
     // elements to prepend to the list
     pub prepend: Vec<String>,
@@ -166,65 +624,315 @@ pub struct ListOps {
     pub append: Vec<String>,
 }
 
+/// Options for cleaning up a PATH-like environment variable, in addition to its
+/// [ListOps]. See [ConfigPathlike] for their meaning.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PathlikeOptions {
+    pub dedupe: bool,
+    pub drop_missing: bool,
+    pub normalize: bool,
+    pub remove: Vec<String>,
+}
+
+impl PathlikeOptions {
+    /// Apply the options a configuration section mentions. Elements to remove are added
+    /// after the existing ones; the other options override the existing ones.
+    fn extend(&mut self, ops: &ConfigPathlike) {
+        let flags = [
+            (&mut self.dedupe, ops.dedupe),
+            (&mut self.drop_missing, ops.drop_missing),
+            (&mut self.normalize, ops.normalize),
+        ];
+        for (flag, value) in flags {
+            if let Some(value) = value {
+                *flag = value;
+            }
+        }
+        self.remove.extend(ops.remove.iter().flatten().cloned());
+    }
+}
+
 /// The disambiguated Wexe Application configuration model, derived from the
 /// [WexeAppConfig] model described by the TOML configuration file.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct WexeApp {
     /// The target executable to run
     pub target: String,
     /// Arguments to prepend and append to the command line
     pub args: ListOps,
+    /// Rules rewriting the arguments given on the command line
+    pub args_rewrite: Vec<ConfigRewriteRule>,
+    /// Translation of path arguments given on the command line
+    pub args_paths: Option<ConfigArgPaths>,
     /// Environment variables to set, override, or delete
     pub env_set: HashMap<String, String>,
     /// Prepending or appending elements to environment variables that are PATH-like
     /// Prepending or appending uses an operating-system-specific separator.
     pub env_pathlike: HashMap<String, ListOps>,
+    /// Cleanup options for PATH-like environment variables (only for the variables
+    /// that have options set)
+    pub env_pathlike_options: HashMap<String, PathlikeOptions>,
+    /// Start the target with an empty environment, except for the variables matching
+    /// one of the [WexeApp::env_keep] patterns
+    pub env_clear: bool,
+    /// Glob patterns for the environment variables kept when [WexeApp::env_clear] is set
+    pub env_keep: Vec<String>,
+    /// How long the target may run before it is stopped, unless the environment
+    /// overrides it (see [crate::launch_timeout::LaunchTimeout])
+    pub timeout: Option<Duration>,
+    /// The grace period between asking the target to terminate and killing it
+    pub kill_after: Option<Duration>,
+    /// When and how often the target is started again if it fails
+    pub retry: Option<RetryPolicy>,
+}
+
+impl ListOps {
+    fn from_lists(prepend: Option<&Vec<String>>, append: Option<&Vec<String>>) -> ListOps {
+        ListOps {
+            prepend: prepend.cloned().unwrap_or_default(),
+            append: append.cloned().unwrap_or_default(),
+        }
+    }
+
+    /// Add the given elements after the existing elements to prepend and append.
+    fn extend(&mut self, prepend: Option<&Vec<String>>, append: Option<&Vec<String>>) {
+        self.prepend.extend(prepend.into_iter().flatten().cloned());
+        self.append.extend(append.into_iter().flatten().cloned());
+    }
+}
+
+impl WexeApp {
+    /// Create a new application model for the given target, with no argument or
+    /// environment changes.
+    pub fn new(target: &str) -> WexeApp {
+        WexeApp {
+            target: target.to_string(),
+            args: ListOps::default(),
+            args_rewrite: Vec::new(),
+            args_paths: None,
+            env_set: HashMap::new(),
+            env_pathlike: HashMap::new(),
+            env_pathlike_options: HashMap::new(),
+            env_clear: false,
+            env_keep: Vec::new(),
+            timeout: None,
+            kill_after: None,
+            retry: None,
+        }
+    }
+
+    /// Disambiguate a configuration file model (without validating it) for operating
+    /// system `os`: the base configuration with the matching `[os.*]` section applied.
+    pub fn from_config_for_os(mut cfg: WexeAppConfig, os: &str) -> WexeApp {
+        let section = cfg.os.take().and_then(|mut sections| sections.remove(os));
+        let mut app = WexeApp::from_config(cfg);
+        if let Some(section) = section {
+            app.apply_override(&section);
+        }
+        app
+    }
+
+    /// Apply the overrides of an `[os.*]` section. Its target replaces the target; its
+    /// arguments, argument rewrite rules, PATH-like elements and `env.keep` patterns are
+    /// added after the existing ones (in their respective lists), and its environment
+    /// variables, `env.clear` and `[args.paths]` section override the existing ones.
+    pub fn apply_override(&mut self, section: &ConfigOverride) {
+        if let Some(target) = &section.target {
+            self.target = target.clone();
+        }
+        self.extend(section.args.as_ref(), section.env.as_ref());
+    }
+
+    /// Apply a profile. Like for [WexeApp::apply_override], its arguments and PATH-like
+    /// elements are added after the existing ones and its environment variables override
+    /// the existing ones.
+    pub fn apply_profile(&mut self, profile: &ConfigProfile) {
+        self.extend(profile.args.as_ref(), profile.env.as_ref());
+    }
+
+    fn extend(&mut self, args: Option<&ConfigArgs>, env: Option<&ConfigEnv>) {
+        if let Some(args) = args {
+            self.args.extend(args.prepend.as_ref(), args.append.as_ref());
+            self.args_rewrite.extend(args.rewrite.iter().flatten().cloned());
+            if let Some(paths) = &args.paths {
+                self.args_paths = Some(paths.clone());
+            }
+        }
+        if let Some(env) = env {
+            for (name, value) in env.set.iter().flatten() {
+                self.env_set.insert(name.clone(), value.clone());
+            }
+            for (name, ops) in env.pathlike.iter().flatten() {
+                let list_ops = self.env_pathlike.entry(name.clone()).or_default();
+                list_ops.extend(ops.prepend.as_ref(), ops.append.as_ref());
+                let options = self.env_pathlike_options.entry(name.clone()).or_default();
+                options.extend(ops);
+                if *options == PathlikeOptions::default() {
+                    self.env_pathlike_options.remove(name);
+                }
+            }
+            if let Some(clear) = env.clear {
+                self.env_clear = clear;
+            }
+            self.env_keep.extend(env.keep.iter().flatten().cloned());
+        }
+    }
+
+    /// Disambiguate a configuration file model (without validating it). The `[os.*]`
+    /// and `[profiles.*]` sections are ignored; see [WexeApp::from_config_for_os].
+    pub fn from_config(cfg: WexeAppConfig) -> WexeApp {
+        let env = cfg.env.unwrap_or(ConfigEnv {
+            set: None,
+            pathlike: None,
+            clear: None,
+            keep: None,
+        });
+        let env_pathlike = env.pathlike.unwrap_or_default();
+        let env_pathlike_options = env_pathlike
+            .iter()
+            .map(|(k, v)| {
+                let mut options = PathlikeOptions::default();
+                options.extend(v);
+                (k.clone(), options)
+            })
+            .filter(|(_, options)| *options != PathlikeOptions::default())
+            .collect();
+        let arguments = cfg.args.unwrap_or_default();
+        WexeApp {
+            target: cfg.target,
+            args: ListOps::from_lists(arguments.prepend.as_ref(), arguments.append.as_ref()),
+            args_rewrite: arguments.rewrite.unwrap_or_default(),
+            args_paths: arguments.paths,
+            env_set: env.set.unwrap_or_default(),
+            env_pathlike: env_pathlike
+                .into_iter()
+                .map(|(k, v)| (k, ListOps::from_lists(v.prepend.as_ref(), v.append.as_ref())))
+                .collect(),
+            env_pathlike_options,
+            env_clear: env.clear.unwrap_or(false),
+            env_keep: env.keep.unwrap_or_default(),
+            timeout: cfg.timeout.as_deref().and_then(parse_duration),
+            kill_after: cfg.kill_after.as_deref().and_then(parse_duration),
+            retry: cfg.retry.as_ref().and_then(|retry| RetryPolicy::from_config(retry).ok()),
+        }
+    }
+}
+
+/// Read a TOML wexe configuration file as-is, without disambiguating or validating it.
+/// # Arguments
+/// * `cfg_file` - The path to the configuration file to read.
+/// # Returns
+/// The [WexeAppConfig] model of the configuration file.
+pub fn read_app_config(cfg_file: &Path) -> Result<WexeAppConfig, ConfigError> {
+    let cfg_text = std::fs::read_to_string(cfg_file).map_err(|source| ConfigError::Unreadable {
+        path: cfg_file.to_path_buf(),
+        source,
+    })?;
+    toml::from_str(&cfg_text).map_err(|e| ConfigError::from_toml(cfg_file, &cfg_text, e))
 }
 
-/// Read a TOML wexe configuration file and return a disambiguated [WexeApp] model for it.
+/// Read a TOML wexe configuration file and return a disambiguated [WexeApp] model for it,
+/// with the `[os.*]` section for the current operating system and the selected profile
+/// (if any; see [WexeAppConfig::select_profile]) applied.
 /// # Arguments
 /// * `cfg_file` - The path to the configuration file to read.
 /// # Returns
 /// A [WexeApp] model derived from the configuration file.
-pub fn read_config_file(cfg_file: PathBuf) -> Result<WexeApp, Box<dyn Error>> {
-    let cfg_text = std::fs::read_to_string(cfg_file)?;
-    let cfg: WexeAppConfig = toml::from_str(&cfg_text)?;
-    let env = cfg.env.unwrap_or(ConfigEnv {
-        set: None,
-        pathlike: None,
-    });
-    let env_pathlike = env.pathlike.unwrap_or(HashMap::new());
-    let arguments = cfg.args.unwrap_or(ConfigArgs {
-        prepend: None,
-        append: None,
-    });
-    let target = Path::new(&cfg.target);
-    let arg_ops = ListOps {
-        prepend: arguments.prepend.unwrap_or(Vec::new()),
-        append: arguments.append.unwrap_or(Vec::new()),
-    };
-    let env_pathlike_ops = env_pathlike
-        .iter()
-        .map(|(k, v)| {
-            let ops = ListOps {
-                prepend: v.prepend.clone().unwrap_or(Vec::new()),
-                append: v.append.clone().unwrap_or(Vec::new()),
-            };
-            (k.clone(), ops)
-        })
-        .collect::<HashMap<String, ListOps>>();
-    let appdef = WexeApp {
-        target: target.to_string_lossy().to_string(),
-        args: arg_ops,
-        env_set: env.set.unwrap_or(HashMap::new()),
-        env_pathlike: env_pathlike_ops,
+pub fn read_config_file(cfg_file: PathBuf) -> Result<WexeApp, ConfigError> {
+    read_config_file_with_profile(cfg_file, None)
+}
+
+/// Like [read_config_file], but with an explicitly requested profile, which must exist.
+/// If `profile` is None, the profile is selected as in [read_config_file].
+pub fn read_config_file_with_profile(
+    cfg_file: PathBuf,
+    profile: Option<&str>,
+) -> Result<WexeApp, ConfigError> {
+    let cfg = read_app_config(&cfg_file)?;
+    resolve_app_config(cfg_file, cfg, profile)
+}
+
+/// Validate a configuration file model read from `cfg_file` and disambiguate it, as
+/// [read_config_file_with_profile] does.
+pub fn resolve_app_config(
+    cfg_file: PathBuf,
+    mut cfg: WexeAppConfig,
+    profile: Option<&str>,
+) -> Result<WexeApp, ConfigError> {
+    let schema = cfg.schema.unwrap_or(0);
+    if schema > CONFIG_SCHEMA {
+        return Err(ConfigError::UnsupportedSchema {
+            path: cfg_file,
+            schema,
+        });
+    }
+    for (key, value) in [("timeout", &cfg.timeout), ("kill_after", &cfg.kill_after)] {
+        if let Some(value) = value
+            && parse_duration(value).is_none()
+        {
+            return Err(ConfigError::InvalidDuration {
+                path: cfg_file,
+                key: key.to_string(),
+                value: value.clone(),
+            });
+        }
+    }
+    if let Some(retry) = &cfg.retry
+        && let Err(message) = RetryPolicy::from_config(retry)
+    {
+        return Err(ConfigError::InvalidRetry {
+            path: cfg_file,
+            message,
+        });
+    }
+    let profile = match cfg.select_profile(profile) {
+        Ok(Some(name)) => cfg.profiles.as_mut().and_then(|profiles| profiles.remove(&name)),
+        Ok(None) => None,
+        Err(profile) => {
+            return Err(ConfigError::UnknownProfile {
+                path: cfg_file,
+                profile,
+            });
+        }
     };
-    if !target.is_absolute() {
-        let error_msg = format!(
-            "Target executable path must be absolute: {:}",
-            appdef.target
-        );
-        return Err(error_msg.into());
+    let mut appdef = WexeApp::from_config_for_os(cfg, env::consts::OS);
+    if let Some(profile) = profile {
+        appdef.apply_profile(&profile);
+    }
+    if !Path::new(&appdef.target).is_absolute() {
+        return Err(ConfigError::NonAbsoluteTarget {
+            path: cfg_file,
+            target: appdef.target,
+        });
+    }
+    for (index, rule) in appdef.args_rewrite.iter().enumerate() {
+        if let Err(message) = RewriteRule::compile(rule) {
+            return Err(ConfigError::InvalidRewriteRule {
+                path: cfg_file,
+                rule: index + 1,
+                message,
+            });
+        }
+    }
+    if let Some(paths) = &appdef.args_paths {
+        for pattern in paths.patterns.iter().flatten() {
+            if let Err(e) = Regex::new(pattern) {
+                return Err(ConfigError::InvalidPathPattern {
+                    path: cfg_file,
+                    pattern: pattern.clone(),
+                    message: e.to_string(),
+                });
+            }
+        }
+    }
+    for pattern in appdef.env_keep.iter() {
+        if let Err(e) = glob::Pattern::new(pattern) {
+            return Err(ConfigError::InvalidKeepPattern {
+                path: cfg_file,
+                pattern: pattern.clone(),
+                message: e.to_string(),
+            });
+        }
     }
     // if !target.exists() {
     //     let error_msg = format!("Target executable does not exist: {:}", appdef.target);
@@ -233,32 +941,520 @@ pub fn read_config_file(cfg_file: PathBuf) -> Result<WexeApp, Box<dyn Error>> {
     Ok(appdef)
 }
 
+/// The template for new configuration files. Its `target` is replaced when it is used.
+const CONFIG_TEMPLATE: &str = include_str!("template.toml");
+
+/// Create the canonical TOML document for an application: the configuration file
+/// template, filled in with the application model.
+pub fn app_to_document(app: &WexeApp) -> DocumentMut {
+    let mut doc = CONFIG_TEMPLATE
+        .parse::<DocumentMut>()
+        .expect("invalid configuration template");
+    // Keep the (empty) PATH section of the template as an example
+    let mut app = app.clone();
+    app.env_pathlike.entry("PATH".to_string()).or_default();
+    update_document(&mut doc, &app);
+    set_schema(&mut doc, CONFIG_SCHEMA);
+    doc
+}
+
+/// Create the text of the canonical configuration file for an application.
+pub fn app_to_toml(app: &WexeApp) -> String {
+    app_to_document(app).to_string()
+}
+
+/// Update a configuration document in place to describe `app`. Values that do not
+/// change are left alone, changed values keep their surrounding comments and
+/// formatting, new values are added in sorted order, and environment variables that
+/// `app` no longer mentions are removed. The `[os.*]` sections are left alone.
+pub fn update_document(doc: &mut DocumentMut, app: &WexeApp) {
+    set_string(doc.as_item_mut(), "target", &app.target);
+    update_list_ops(doc.as_table_mut(), "args", &app.args);
+    update_rewrite_rules(doc.as_table_mut(), &app.args_rewrite);
+    update_arg_paths(doc.as_table_mut(), app.args_paths.as_ref());
+    let has_env = !app.env_set.is_empty()
+        || !app.env_pathlike.is_empty()
+        || app.env_clear
+        || !app.env_keep.is_empty();
+    if !has_env && !doc.contains_key("env") {
+        return;
+    }
+    let env = child_table(doc.as_table_mut(), "env");
+    if app.env_clear || env.contains_key("clear") {
+        set_bool_in(env, "clear", app.env_clear);
+    }
+    if !app.env_keep.is_empty() || env.contains_key("keep") {
+        set_string_array(env, "keep", &app.env_keep);
+    }
+    if !app.env_set.is_empty() || env.contains_key("set") {
+        let set = child_table(env, "set");
+        let stale: Vec<String> = set
+            .iter()
+            .map(|(k, _)| k.to_string())
+            .filter(|k| !app.env_set.contains_key(k))
+            .collect();
+        for k in stale {
+            set.remove(&k);
+        }
+        let mut names: Vec<&String> = app.env_set.keys().collect();
+        names.sort();
+        for name in names {
+            set_string_in(set, name, &app.env_set[name]);
+        }
+    }
+    if !app.env_pathlike.is_empty() || env.contains_key("pathlike") {
+        let pathlike = child_table(env, "pathlike");
+        let stale: Vec<String> = pathlike
+            .iter()
+            .map(|(k, _)| k.to_string())
+            .filter(|k| !app.env_pathlike.contains_key(k))
+            .collect();
+        for k in stale {
+            pathlike.remove(&k);
+        }
+        let mut names: Vec<&String> = app.env_pathlike.keys().collect();
+        names.sort();
+        for name in names {
+            update_list_ops(pathlike, name, &app.env_pathlike[name]);
+            let options = app.env_pathlike_options.get(name).cloned().unwrap_or_default();
+            update_pathlike_options(pathlike, name, &options);
+        }
+    }
+}
+
+/// Update the cleanup options of the PATH-like variable table `key` in `parent`.
+/// Options that are not set are only written if the table already mentions them.
+fn update_pathlike_options(parent: &mut dyn TableLike, key: &str, options: &PathlikeOptions) {
+    if *options == PathlikeOptions::default() && !parent.contains_key(key) {
+        return;
+    }
+    let table = child_table(parent, key);
+    let flags = [
+        ("dedupe", options.dedupe),
+        ("drop_missing", options.drop_missing),
+        ("normalize", options.normalize),
+    ];
+    for (name, flag) in flags {
+        if flag || table.contains_key(name) {
+            set_bool_in(table, name, flag);
+        }
+    }
+    if !options.remove.is_empty() || table.contains_key("remove") {
+        set_string_array(table, "remove", &options.remove);
+    }
+}
+
+/// Replace the `[os.<name>]` section of a configuration document by `section`.
+pub fn set_os_section(doc: &mut DocumentMut, name: &str, section: &ConfigOverride) {
+    let table = replace_section(doc, "os", name);
+    if let Some(target) = &section.target {
+        set_string_in(table, "target", target);
+    }
+    write_section(table, section.args.as_ref(), section.env.as_ref());
+}
+
+/// Replace the `[profiles.<name>]` section of a configuration document by `profile`.
+pub fn set_profile_section(doc: &mut DocumentMut, name: &str, profile: &ConfigProfile) {
+    let table = replace_section(doc, "profiles", name);
+    write_section(table, profile.args.as_ref(), profile.env.as_ref());
+}
+
+/// Replace the `[retry]` section of a configuration document by `retry`.
+pub fn set_retry_section(doc: &mut DocumentMut, retry: &ConfigRetry) {
+    doc.remove("retry");
+    let table = child_table(doc.as_table_mut(), "retry");
+    if let Some(attempts) = retry.attempts {
+        table.insert("attempts", toml_edit::value(i64::from(attempts)));
+    }
+    if let Some(backoff) = &retry.backoff {
+        set_string_in(table, "backoff", backoff);
+    }
+    if let Some(delay) = &retry.delay {
+        set_string_in(table, "delay", delay);
+    }
+    if let Some(codes) = &retry.on_exit_codes {
+        let codes: toml_edit::Array = codes.iter().map(|code| i64::from(*code)).collect();
+        table.insert("on_exit_codes", toml_edit::value(codes));
+    }
+    if let Some(on_signal) = retry.on_signal {
+        set_bool_in(table, "on_signal", on_signal);
+    }
+    if let Some(piped_stdin) = retry.piped_stdin {
+        set_bool_in(table, "piped_stdin", piped_stdin);
+    }
+}
+
+/// Remove the table `[<group>.<name>]` from a document and create it anew (empty).
+fn replace_section<'a>(doc: &'a mut DocumentMut, group: &str, name: &str) -> &'a mut dyn TableLike {
+    let group = child_table(doc.as_table_mut(), group);
+    group.remove(name);
+    child_table(group, name)
+}
+
+/// Write the arguments and environment changes of a conditional section into its table.
+fn write_section(table: &mut dyn TableLike, args: Option<&ConfigArgs>, env: Option<&ConfigEnv>) {
+    if let Some(args) = args {
+        let list_ops = ListOps::from_lists(args.prepend.as_ref(), args.append.as_ref());
+        update_list_ops(table, "args", &list_ops);
+        update_rewrite_rules(table, args.rewrite.as_deref().unwrap_or_default());
+        update_arg_paths(table, args.paths.as_ref());
+    }
+    let Some(env) = env else {
+        return;
+    };
+    let env_table = child_table(table, "env");
+    if let Some(clear) = env.clear {
+        set_bool_in(env_table, "clear", clear);
+    }
+    if let Some(keep) = &env.keep {
+        set_string_array(env_table, "keep", keep);
+    }
+    if let Some(set) = &env.set {
+        let set_table = child_table(env_table, "set");
+        let mut names: Vec<&String> = set.keys().collect();
+        names.sort();
+        for name in names {
+            set_string_in(set_table, name, &set[name]);
+        }
+    }
+    if let Some(pathlike) = &env.pathlike {
+        let pathlike_table = child_table(env_table, "pathlike");
+        let mut names: Vec<&String> = pathlike.keys().collect();
+        names.sort();
+        for name in names {
+            let ops = &pathlike[name];
+            let list_ops = ListOps::from_lists(ops.prepend.as_ref(), ops.append.as_ref());
+            update_list_ops(pathlike_table, name, &list_ops);
+            let flags = [
+                ("dedupe", ops.dedupe),
+                ("drop_missing", ops.drop_missing),
+                ("normalize", ops.normalize),
+            ];
+            if flags.iter().all(|(_, flag)| flag.is_none()) && ops.remove.is_none() {
+                continue;
+            }
+            let table = child_table(pathlike_table, name);
+            for (key, flag) in flags {
+                if let Some(flag) = flag {
+                    set_bool_in(table, key, flag);
+                }
+            }
+            if let Some(remove) = &ops.remove {
+                set_string_array(table, "remove", remove);
+            }
+        }
+    }
+}
+
+/// Update the `[[args.rewrite]]` rules of the `args` table in `parent`. Existing rules
+/// are left alone if they do not change.
+fn update_rewrite_rules(parent: &mut dyn TableLike, rules: &[ConfigRewriteRule]) {
+    let existing = parent
+        .get("args")
+        .and_then(|args| args.get("rewrite"))
+        .and_then(|rewrite| rewrite.as_array_of_tables());
+    let unchanged = match existing {
+        Some(tables) => {
+            tables.len() == rules.len()
+                && tables.iter().zip(rules).all(|(table, rule)| rule_from_table(table) == *rule)
+        }
+        None => rules.is_empty(),
+    };
+    if unchanged {
+        return;
+    }
+    let args = child_table(parent, "args");
+    if rules.is_empty() {
+        args.remove("rewrite");
+        return;
+    }
+    let mut tables = ArrayOfTables::new();
+    for rule in rules {
+        let mut table = Table::new();
+        let fields = [
+            ("match", &rule.pattern),
+            ("replace", &rule.replace),
+            ("insert", &rule.insert),
+            ("unless", &rule.unless),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                table.insert(key, toml_edit::value(value.as_str()));
+            }
+        }
+        if let Some(drop) = rule.drop {
+            table.insert("drop", toml_edit::value(drop));
+        }
+        tables.push(table);
+    }
+    args.insert("rewrite", Item::ArrayOfTables(tables));
+}
+
+/// Read an `[[args.rewrite]]` rule from its table.
+fn rule_from_table(table: &Table) -> ConfigRewriteRule {
+    let string = |key: &str| table.get(key).and_then(|v| v.as_str()).map(str::to_string);
+    ConfigRewriteRule {
+        pattern: string("match"),
+        replace: string("replace"),
+        drop: table.get("drop").and_then(|v| v.as_bool()),
+        insert: string("insert"),
+        unless: string("unless"),
+    }
+}
+
+/// Update the `[args.paths]` section of the `args` table in `parent`. The existing
+/// section is left alone if it does not change.
+fn update_arg_paths(parent: &mut dyn TableLike, paths: Option<&ConfigArgPaths>) {
+    let existing = parent
+        .get("args")
+        .and_then(|args| args.get("paths"))
+        .and_then(|paths| paths.as_table())
+        .map(paths_from_table);
+    if existing.as_ref() == paths {
+        return;
+    }
+    let args = child_table(parent, "args");
+    let Some(paths) = paths else {
+        args.remove("paths");
+        return;
+    };
+    let mut table = Table::new();
+    for (key, value) in [("absolute", paths.absolute), ("existing", paths.existing)] {
+        if let Some(value) = value {
+            table.insert(key, toml_edit::value(value));
+        }
+    }
+    if let Some(patterns) = &paths.patterns {
+        table.insert("match", toml_edit::value(Array::from_iter(patterns.iter())));
+    }
+    if let Some(mappings) = &paths.map {
+        let mut tables = ArrayOfTables::new();
+        for mapping in mappings {
+            let mut map_table = Table::new();
+            map_table.insert("from", toml_edit::value(mapping.from.as_str()));
+            map_table.insert("to", toml_edit::value(mapping.to.as_str()));
+            if let Some(separator) = &mapping.separator {
+                map_table.insert("separator", toml_edit::value(separator.as_str()));
+            }
+            tables.push(map_table);
+        }
+        table.insert("map", Item::ArrayOfTables(tables));
+    }
+    args.insert("paths", Item::Table(table));
+}
+
+/// Read an `[args.paths]` section from its table.
+fn paths_from_table(table: &Table) -> ConfigArgPaths {
+    let string = |table: &Table, key: &str| {
+        table.get(key).and_then(|v| v.as_str()).map(str::to_string)
+    };
+    ConfigArgPaths {
+        absolute: table.get("absolute").and_then(|v| v.as_bool()),
+        existing: table.get("existing").and_then(|v| v.as_bool()),
+        patterns: table.get("match").and_then(|v| v.as_array()).map(|patterns| {
+            patterns.iter().filter_map(|v| v.as_str()).map(str::to_string).collect()
+        }),
+        map: table.get("map").and_then(|v| v.as_array_of_tables()).map(|tables| {
+            tables
+                .iter()
+                .map(|map| ConfigPathMapping {
+                    from: string(map, "from").unwrap_or_default(),
+                    to: string(map, "to").unwrap_or_default(),
+                    separator: string(map, "separator"),
+                })
+                .collect()
+        }),
+    }
+}
+
+/// Get the sub-table `key` of `parent`, creating it if it does not exist yet.
+fn child_table<'a>(parent: &'a mut dyn TableLike, key: &str) -> &'a mut dyn TableLike {
+    if !parent.get(key).is_some_and(|item| item.is_table_like()) {
+        let mut table = Table::new();
+        table.set_implicit(true);
+        parent.insert(key, Item::Table(table));
+    }
+    parent.get_mut(key).unwrap().as_table_like_mut().unwrap()
+}
+
+/// Set a string value in a table, keeping the formatting of an existing value.
+fn set_string_in(table: &mut dyn TableLike, key: &str, text: &str) {
+    match table.get_mut(key).and_then(|item| item.as_value_mut()) {
+        Some(value) => {
+            if value.as_str() != Some(text) {
+                let decor = value.decor().clone();
+                *value = Value::from(text);
+                *value.decor_mut() = decor;
+            }
+        }
+        None => {
+            table.insert(key, toml_edit::value(text));
+        }
+    }
+}
+
+/// Set a boolean value in a table, keeping the formatting of an existing value.
+fn set_bool_in(table: &mut dyn TableLike, key: &str, flag: bool) {
+    match table.get_mut(key).and_then(|item| item.as_value_mut()) {
+        Some(value) => {
+            if value.as_bool() != Some(flag) {
+                let decor = value.decor().clone();
+                *value = Value::from(flag);
+                *value.decor_mut() = decor;
+            }
+        }
+        None => {
+            table.insert(key, toml_edit::value(flag));
+        }
+    }
+}
+
+fn set_string(item: &mut Item, key: &str, text: &str) {
+    if let Some(table) = item.as_table_like_mut() {
+        set_string_in(table, key, text);
+    }
+}
+
+/// Set a list of strings in a table, one element per line. If an existing list has
+/// the same length, only its changed elements are replaced (keeping their formatting).
+fn set_string_array(table: &mut dyn TableLike, key: &str, values: &[String]) {
+    if let Some(array) = table.get_mut(key).and_then(|item| item.as_array_mut())
+        && array.len() == values.len()
+    {
+        for (value, text) in array.iter_mut().zip(values) {
+            if value.as_str() != Some(text.as_str()) {
+                let decor = value.decor().clone();
+                *value = Value::from(text.as_str());
+                *value.decor_mut() = decor;
+            }
+        }
+        return;
+    }
+    let mut array = Array::new();
+    for text in values {
+        let mut value = Value::from(text.as_str());
+        value.decor_mut().set_prefix("\n  ");
+        array.push_formatted(value);
+    }
+    if values.is_empty() {
+        array.set_trailing(" ");
+    } else {
+        array.set_trailing("\n");
+        array.set_trailing_comma(true);
+    }
+    let mut new_value = Value::Array(array);
+    if let Some(old_value) = table.get(key).and_then(|item| item.as_value()) {
+        *new_value.decor_mut() = old_value.decor().clone();
+    }
+    match table.get_mut(key) {
+        Some(item) if item.is_value() => *item = Item::Value(new_value),
+        _ => {
+            table.insert(key, Item::Value(new_value));
+        }
+    }
+}
+
+/// Update the `prepend` and `append` lists of the table `key` in `parent`. The table
+/// is only created if there is something to put in it.
+fn update_list_ops(parent: &mut dyn TableLike, key: &str, ops: &ListOps) {
+    if ops.prepend.is_empty() && ops.append.is_empty() && !parent.contains_key(key) {
+        return;
+    }
+    let table = child_table(parent, key);
+    set_string_array(table, "prepend", &ops.prepend);
+    set_string_array(table, "append", &ops.append);
+}
+
+/// A step upgrading a configuration document from schema `from` to schema `from + 1`.
+/// The `schema` key itself is updated by [migrate_document].
+struct SchemaMigration {
+    from: i64,
+    description: &'static str,
+    migrate: fn(&mut DocumentMut),
+}
+
+/// The upgrade steps, in order. Add a step here whenever [CONFIG_SCHEMA] is increased.
+const SCHEMA_MIGRATIONS: &[SchemaMigration] = &[SchemaMigration {
+    from: 0,
+    description: "Declare the configuration schema",
+    // Schema 1 has the same content as unversioned files
+    migrate: |_| {},
+}];
+
+/// Get the configuration schema a document declares (0 if it has no `schema` key).
+/// Returns None if the `schema` key is not an integer.
+pub fn document_schema(doc: &DocumentMut) -> Option<i64> {
+    match doc.get("schema") {
+        Some(item) => item.as_integer(),
+        None => Some(0),
+    }
+}
+
+/// Upgrade a configuration document to the current schema ([CONFIG_SCHEMA]), editing
+/// it in place so comments and formatting survive. `path` is only used in errors.
+/// # Returns
+/// The descriptions of the upgrade steps applied (empty if the document was current).
+pub fn migrate_document(path: &Path, doc: &mut DocumentMut) -> Result<Vec<&'static str>, ConfigError> {
+    let mut schema = document_schema(doc).ok_or_else(|| ConfigError::Parse {
+        path: path.to_path_buf(),
+        message: "The 'schema' key must be an integer".to_string(),
+        span: None,
+        line_col: None,
+    })?;
+    if schema > CONFIG_SCHEMA {
+        return Err(ConfigError::UnsupportedSchema {
+            path: path.to_path_buf(),
+            schema,
+        });
+    }
+    let mut applied = Vec::new();
+    for step in SCHEMA_MIGRATIONS.iter() {
+        if step.from < schema {
+            continue;
+        }
+        (step.migrate)(doc);
+        applied.push(step.description);
+        schema = step.from + 1;
+    }
+    if !applied.is_empty() {
+        set_schema(doc, schema);
+    }
+    Ok(applied)
+}
+
+/// Set the `schema` key of a document, keeping the formatting of an existing value.
+fn set_schema(doc: &mut DocumentMut, schema: i64) {
+    match doc.get_mut("schema").and_then(|item| item.as_value_mut()) {
+        Some(value) => {
+            if value.as_integer() != Some(schema) {
+                let decor = value.decor().clone();
+                *value = Value::from(schema);
+                *value.decor_mut() = decor;
+            }
+        }
+        None => {
+            doc.insert("schema", toml_edit::value(schema));
+        }
+    }
+}
+
 /// Get the configuration file for the wexecfg application. This configuration is
 /// hardcoded here, derived from the wexe executable location.
-pub fn wexecfg_config_file() -> WexeApp {
-    let exe = env::current_exe().expect("Could not get the current executable path.");
-    let folder = exe
-        .parent()
-        .expect("Could not get the executable's parent folder.");
-    let extension = exe.extension();
-    let wexecfg_file_name = match extension {
-        Some(ext) => format!("wexecfg.{}", ext.to_str().unwrap()),
-        None => "wexecfg".to_string(),
-    };
+pub fn wexecfg_config_file() -> Result<WexeApp, ConfigError> {
+    let exe = env::current_exe().map_err(ConfigError::NoExecutablePath)?;
+    let folder = exe.parent().unwrap_or(Path::new(""));
+    let mut wexecfg_file_name = OsString::from("wexecfg");
+    if let Some(ext) = exe.extension() {
+        wexecfg_file_name.push(".");
+        wexecfg_file_name.push(ext);
+    }
     let wexecfg_path = folder.join(wexecfg_file_name);
     if !wexecfg_path.exists() {
-        panic!(
-            "Could not find the wexecfg executable file: {:?}",
-            wexecfg_path
-        );
-    }
-    WexeApp {
-        target: wexecfg_path.to_str().unwrap().to_string(),
-        args: ListOps {
-            prepend: Vec::new(),
-            append: Vec::new(),
-        },
-        env_set: HashMap::new(),
-        env_pathlike: HashMap::new(),
+        return Err(ConfigError::WexecfgNotFound(wexecfg_path));
     }
+    // Targets are strings; a lossy conversion would produce a path that does not exist
+    let Some(target) = wexecfg_path.to_str() else {
+        return Err(ConfigError::NonUnicodePath(wexecfg_path));
+    };
+    Ok(WexeApp::new(target))
 }
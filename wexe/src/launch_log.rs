@@ -0,0 +1,124 @@
+//! Logging launches of wexe applications: a JSON record per launch, appended to the log
+//! file configured by the `log` key of the application or of the repository settings.
+//! The log file is rotated when it grows beyond a size limit.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::config_model::{WexeSettings, get_wexe_cfg_dir};
+
+/// The default size (in bytes) beyond which a log file is rotated
+pub const DEFAULT_LOG_MAX_SIZE: u64 = 1024 * 1024;
+
+/// The default number of rotated log files kept (as `<log>.1`, `<log>.2`, ...)
+pub const DEFAULT_LOG_KEEP: usize = 3;
+
+/// A record of one launch of an application, written as a line of JSON. Values that are
+/// not valid Unicode are recorded lossily.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LaunchRecord {
+    /// When the target was started (RFC 3339, local time)
+    pub timestamp: String,
+    /// The application tag
+    pub tag: String,
+    /// The configuration file of the application
+    pub config: String,
+    /// The complete command line of the target, starting with the target itself
+    pub argv: Vec<String>,
+    /// The changes to the inherited environment: the new value of each variable, or
+    /// None if it was removed
+    pub env: BTreeMap<String, Option<String>>,
+    /// Whether the environment was cleared (see `env.clear`) before the changes
+    pub env_cleared: bool,
+    /// The working directory of the target
+    pub cwd: String,
+    /// The process ID of the target, if it was started
+    pub pid: Option<u32>,
+    /// How long the target ran, in milliseconds
+    pub duration_ms: u64,
+    /// The exit code of the target, if it exited normally
+    pub exit_code: Option<i32>,
+    /// The signal that terminated the target, if any (Unix only)
+    pub signal: Option<i32>,
+    /// Whether the target was stopped because its timeout expired
+    pub timed_out: bool,
+    /// How many times the target was started (more than once if it was retried)
+    pub attempts: u32,
+    /// Why the target could not be run, if it could not
+    pub error: Option<String>,
+}
+
+/// A launch log file, with its rotation limits
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaunchLog {
+    pub path: PathBuf,
+    /// The size (in bytes) beyond which the log is rotated; 0 disables rotation
+    pub max_size: u64,
+    /// The number of rotated log files kept
+    pub keep: usize,
+}
+
+impl LaunchLog {
+    /// Get the launch log for an application: its own `log` file (`app_log`) if it has
+    /// one, else the `log` file of the repository settings, if any. Relative paths are
+    /// relative to the wexe configuration folder.
+    pub fn for_app(app_log: Option<&str>, settings: &WexeSettings) -> Option<LaunchLog> {
+        let log = app_log.or(settings.log.as_deref())?;
+        Some(LaunchLog {
+            path: get_wexe_cfg_dir().join(log),
+            max_size: settings.log_max_size.unwrap_or(DEFAULT_LOG_MAX_SIZE),
+            keep: settings.log_keep.unwrap_or(DEFAULT_LOG_KEEP),
+        })
+    }
+
+    /// The path of the rotated log file number `n` (1 is the most recent)
+    pub fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut path = OsString::from(self.path.as_os_str());
+        path.push(format!(".{n}"));
+        PathBuf::from(path)
+    }
+
+    /// Rotate the log files: the log becomes rotated file 1, rotated file 1 becomes 2,
+    /// and so on. The oldest file beyond the number kept is removed.
+    fn rotate(&self) -> io::Result<()> {
+        if self.keep == 0 {
+            return fs::remove_file(&self.path);
+        }
+        let oldest = self.rotated_path(self.keep);
+        if oldest.exists() {
+            fs::remove_file(oldest)?;
+        }
+        for n in (1..self.keep).rev() {
+            let rotated = self.rotated_path(n);
+            if rotated.exists() {
+                fs::rename(rotated, self.rotated_path(n + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))
+    }
+
+    /// Append a record to the log, rotating it first if it has grown beyond its limit.
+    /// The folder of the log is created if needed.
+    pub fn append(&self, record: &LaunchRecord) -> io::Result<()> {
+        if let Some(folder) = self.path.parent().filter(|folder| !folder.as_os_str().is_empty()) {
+            fs::create_dir_all(folder)?;
+        }
+        let size = fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+        if self.max_size > 0 && size >= self.max_size {
+            self.rotate()?;
+        }
+        let mut line = serde_json::to_string(record).map_err(io::Error::other)?;
+        line.push('\n');
+        // A single write, so concurrent launches do not interleave their records
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())
+    }
+}
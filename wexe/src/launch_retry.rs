@@ -0,0 +1,170 @@
+//! Retrying failed launches of wexe applications: the `[retry]` section of a configuration
+//! starts the target again when it exits with one of a list of codes (or is terminated by
+//! a signal), waiting a fixed or exponentially growing delay between attempts.
+//! A retried target must see the same standard input as the first attempt, so retries
+//! are only done if standard input is a terminal or can be rewound, unless the
+//! configuration opts in with `piped_stdin`.
+
+use std::fs::File;
+use std::io::{self, IsTerminal, Seek, SeekFrom};
+use std::process::ExitStatus;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::config_model::ConfigRetry;
+use crate::launch_timeout::parse_duration;
+
+/// The delay before the first retry, if the `[retry]` section does not set `delay`
+pub const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// How the delay between attempts grows
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum Backoff {
+    /// The same delay before each retry
+    Fixed,
+    /// The delay doubles with each retry
+    Exponential,
+}
+
+/// When and how often a failed target is started again
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RetryPolicy {
+    /// The maximum number of times the target is started, including the first
+    pub attempts: u32,
+    pub backoff: Backoff,
+    /// The delay before the first retry
+    pub delay: Duration,
+    /// The exit codes that cause a retry; empty for any non-zero exit code
+    pub on_exit_codes: Vec<i32>,
+    /// Whether the target being terminated by a signal causes a retry
+    pub on_signal: bool,
+    /// Whether to retry even if standard input cannot be replayed
+    pub piped_stdin: bool,
+}
+
+impl RetryPolicy {
+    /// Validate the `[retry]` section of a configuration.
+    /// # Returns
+    /// The retry policy, or a message telling what is wrong with the section.
+    pub fn from_config(cfg: &ConfigRetry) -> Result<RetryPolicy, String> {
+        let attempts = cfg.attempts.unwrap_or(1);
+        if attempts == 0 {
+            return Err("attempts must be at least 1".to_string());
+        }
+        let backoff = match cfg.backoff.as_deref() {
+            None | Some("fixed") => Backoff::Fixed,
+            Some("exponential") => Backoff::Exponential,
+            Some(other) => {
+                return Err(format!(
+                    "unknown backoff '{other}' (expecting 'fixed' or 'exponential')"
+                ));
+            }
+        };
+        let delay = match cfg.delay.as_deref() {
+            Some(delay) => {
+                parse_duration(delay).ok_or_else(|| format!("invalid delay '{delay}'"))?
+            }
+            None => DEFAULT_RETRY_DELAY,
+        };
+        if cfg.on_exit_codes.as_ref().is_some_and(|codes| codes.contains(&0)) {
+            return Err("on_exit_codes cannot contain 0".to_string());
+        }
+        Ok(RetryPolicy {
+            attempts,
+            backoff,
+            delay,
+            on_exit_codes: cfg.on_exit_codes.clone().unwrap_or_default(),
+            on_signal: cfg.on_signal.unwrap_or(false),
+            piped_stdin: cfg.piped_stdin.unwrap_or(false),
+        })
+    }
+
+    /// Whether an attempt that ended with `status` is retried (if attempts are left)
+    pub fn should_retry(&self, status: &ExitStatus) -> bool {
+        match status.code() {
+            Some(0) => false,
+            Some(code) => self.on_exit_codes.is_empty() || self.on_exit_codes.contains(&code),
+            // Without an exit code, the target was terminated by a signal
+            None => self.on_signal,
+        }
+    }
+
+    /// The delay before retry number `retry` (1 for the second attempt)
+    pub fn delay_before(&self, retry: u32) -> Duration {
+        match self.backoff {
+            Backoff::Fixed => self.delay,
+            Backoff::Exponential => {
+                self.delay.saturating_mul(1 << retry.saturating_sub(1).min(20))
+            }
+        }
+    }
+}
+
+/// How standard input is given again to a retried target. The target inherits the
+/// standard input of wexe, so this is about what is left of it for the next attempt.
+#[derive(Debug)]
+pub enum StdinReplay {
+    /// Nothing needs to be done: standard input is a terminal (the user can answer
+    /// again) or the null device, or the configuration accepts that the next attempt
+    /// only sees what the previous ones did not read
+    Nothing,
+    /// Standard input is a file, rewound to its original position before each retry
+    /// (the handle shares its position with the standard input of the target)
+    Rewind(File, u64),
+}
+
+impl StdinReplay {
+    /// Determine how the current standard input can be replayed for a retry.
+    /// # Returns
+    /// None if it cannot be (for instance because it is a pipe).
+    pub fn detect() -> Option<StdinReplay> {
+        let stdin = io::stdin();
+        if stdin.is_terminal() || is_null_device() {
+            return Some(StdinReplay::Nothing);
+        }
+        let mut file = stdin_file(&stdin)?;
+        if !file.metadata().ok()?.is_file() {
+            return None;
+        }
+        let position = file.stream_position().ok()?;
+        Some(StdinReplay::Rewind(file, position))
+    }
+
+    /// Prepare standard input for the next attempt
+    pub fn rewind(&mut self) -> io::Result<()> {
+        match self {
+            StdinReplay::Nothing => Ok(()),
+            StdinReplay::Rewind(file, position) => {
+                file.seek(SeekFrom::Start(*position)).map(|_| ())
+            }
+        }
+    }
+}
+
+/// Whether standard input is the null device (so every attempt reads nothing)
+#[cfg(unix)]
+fn is_null_device() -> bool {
+    match (same_file::Handle::stdin(), same_file::Handle::from_path("/dev/null")) {
+        (Ok(stdin), Ok(null)) => stdin == null,
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_null_device() -> bool {
+    false
+}
+
+/// Get a file handle sharing its position with standard input
+#[cfg(unix)]
+fn stdin_file(stdin: &io::Stdin) -> Option<File> {
+    use std::os::fd::AsFd;
+    stdin.as_fd().try_clone_to_owned().ok().map(File::from)
+}
+
+#[cfg(windows)]
+fn stdin_file(stdin: &io::Stdin) -> Option<File> {
+    use std::os::windows::io::AsHandle;
+    stdin.as_handle().try_clone_to_owned().ok().map(File::from)
+}
@@ -0,0 +1,204 @@
+//! Timeouts for wexe applications: a target that runs longer than the `timeout` of its
+//! configuration (or of the `WEXE_TIMEOUT` environment variable) is asked to terminate
+//! (SIGTERM on Unix), and killed if it is still running `kill_after` later. Like
+//! `timeout(1)`, wexe then exits with code [TIMEOUT_EXIT_CODE].
+
+use std::env;
+use std::io;
+use std::process::{Child, ExitStatus};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config_model::wexe_dbg;
+use crate::console_colors::*;
+use crate::launcher::LaunchError;
+
+/// The exit code of wexe when the target was stopped because its timeout expired
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// The grace period between terminating and killing the target, if `kill_after` is not set
+pub const DEFAULT_KILL_AFTER: Duration = Duration::from_secs(10);
+
+/// The environment variable overriding the `timeout` of the application ("0" disables it)
+pub const WEXE_TIMEOUT_VAR: &str = "WEXE_TIMEOUT";
+
+/// The environment variable overriding the `kill_after` of the application
+pub const WEXE_KILL_AFTER_VAR: &str = "WEXE_KILL_AFTER";
+
+/// How often a target with a timeout is checked for having exited
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Parse a duration: a number with an optional unit, `ms`, `s` (the default), `m`, `h`
+/// or `d`, as in `"500ms"`, `"10"`, `"1.5h"`.
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let seconds = match unit.trim() {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        "d" => number * 86400.0,
+        _ => return None,
+    };
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// Format a duration the way [parse_duration] reads it back
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    match millis {
+        0 => "0s".to_string(),
+        _ if millis.is_multiple_of(3_600_000) => format!("{}h", millis / 3_600_000),
+        _ if millis.is_multiple_of(60_000) => format!("{}m", millis / 60_000),
+        _ if millis.is_multiple_of(1000) => format!("{}s", millis / 1000),
+        _ => format!("{millis}ms"),
+    }
+}
+
+/// The timeout of a launch, and the grace period before the target is killed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LaunchTimeout {
+    pub timeout: Duration,
+    pub kill_after: Duration,
+}
+
+/// How a target with a timeout ended
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeoutOutcome {
+    /// The target exited before its timeout expired
+    Exited,
+    /// The timeout expired, and the target exited after being asked to terminate
+    Terminated,
+    /// The timeout expired, and the target was killed after the grace period
+    Killed,
+}
+
+/// Parse the duration `value` of `setting` (a configuration key or environment variable).
+fn parse_setting(setting: &str, value: &str) -> Result<Duration, LaunchError> {
+    parse_duration(value).ok_or_else(|| LaunchError::InvalidDuration {
+        setting: setting.to_string(),
+        value: value.to_string(),
+    })
+}
+
+/// Read a duration from the environment variable `variable`, if it is set and not empty.
+fn env_duration(variable: &str) -> Result<Option<Duration>, LaunchError> {
+    match env::var(variable) {
+        Ok(value) if !value.is_empty() => parse_setting(variable, &value).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Read a duration from the environment variable `variable` or else from the configuration
+/// key `key` (with value `configured`).
+fn duration_setting(
+    variable: &str,
+    key: &str,
+    configured: Option<&str>,
+) -> Result<Option<Duration>, LaunchError> {
+    match env_duration(variable)? {
+        Some(duration) => Ok(Some(duration)),
+        None => configured.map(|value| parse_setting(key, value)).transpose(),
+    }
+}
+
+impl LaunchTimeout {
+    /// Get the timeout of an application from its `timeout` and `kill_after` keys, which
+    /// the [WEXE_TIMEOUT_VAR] and [WEXE_KILL_AFTER_VAR] environment variables override.
+    /// # Returns
+    /// None if the application has no timeout (or a zero timeout).
+    pub fn for_app(
+        timeout: Option<&str>,
+        kill_after: Option<&str>,
+    ) -> Result<Option<LaunchTimeout>, LaunchError> {
+        let timeout = duration_setting(WEXE_TIMEOUT_VAR, "timeout", timeout)?;
+        let kill_after = duration_setting(WEXE_KILL_AFTER_VAR, "kill_after", kill_after)?;
+        Ok(LaunchTimeout::from_settings(timeout, kill_after))
+    }
+
+    /// Like [LaunchTimeout::for_app], for the durations of an application model
+    /// (see [crate::config_model::WexeApp::timeout]).
+    pub fn for_durations(
+        timeout: Option<Duration>,
+        kill_after: Option<Duration>,
+    ) -> Result<Option<LaunchTimeout>, LaunchError> {
+        let timeout = env_duration(WEXE_TIMEOUT_VAR)?.or(timeout);
+        let kill_after = env_duration(WEXE_KILL_AFTER_VAR)?.or(kill_after);
+        Ok(LaunchTimeout::from_settings(timeout, kill_after))
+    }
+
+    fn from_settings(timeout: Option<Duration>, kill_after: Option<Duration>) -> Option<LaunchTimeout> {
+        timeout.filter(|timeout| !timeout.is_zero()).map(|timeout| LaunchTimeout {
+            timeout,
+            kill_after: kill_after.unwrap_or(DEFAULT_KILL_AFTER),
+        })
+    }
+
+    /// Wait for a child process, stopping it if it runs longer than the timeout: it is
+    /// first asked to terminate (SIGTERM; on other platforms it is killed right away),
+    /// then killed if it still runs after the grace period. A zero grace period kills
+    /// the child right away.
+    pub fn wait(&self, child: &mut Child) -> io::Result<(ExitStatus, TimeoutOutcome)> {
+        if let Some(status) = wait_until(child, Instant::now() + self.timeout)? {
+            return Ok((status, TimeoutOutcome::Exited));
+        }
+        if !self.kill_after.is_zero() && terminate(child)? {
+            if wexe_dbg() {
+                eprintln!(
+                    "{bg_B}Timeout of {fg_o}{}{rst}{bg_B} expired; sent SIGTERM to process {fg_g}{}{rst}.",
+                    format_duration(self.timeout),
+                    child.id()
+                );
+            }
+            if let Some(status) = wait_until(child, Instant::now() + self.kill_after)? {
+                return Ok((status, TimeoutOutcome::Terminated));
+            }
+        }
+        if wexe_dbg() {
+            eprintln!(
+                "{bg_B}Timeout of {fg_o}{}{rst}{bg_B} expired; killing process {fg_g}{}{rst}.",
+                format_duration(self.timeout),
+                child.id()
+            );
+        }
+        child.kill()?;
+        Ok((child.wait()?, TimeoutOutcome::Killed))
+    }
+}
+
+/// Wait for a child process until `deadline`, returning its exit status if it exited
+fn wait_until(child: &mut Child, deadline: Instant) -> io::Result<Option<ExitStatus>> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+/// Ask a child process to terminate (SIGTERM). Returns false if that is not supported
+/// on this platform.
+#[cfg(unix)]
+fn terminate(child: &Child) -> io::Result<bool> {
+    // SAFETY: kill() has no memory safety requirements; the child has not been reaped
+    // yet (try_wait() did not return its status), so its PID cannot have been reused
+    let result = unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(true)
+}
+
+#[cfg(not(unix))]
+fn terminate(_child: &Child) -> io::Result<bool> {
+    Ok(false)
+}
@@ -0,0 +1,539 @@
+//! Launching the target of a wexe application: the logic behind the wexe executable,
+//! available to other tools that want to run wexe applications themselves.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Child, Command, ExitStatus};
+use std::thread;
+
+use crate::args_rewrite::{ArgRewriter, PathTranslator};
+use crate::config_model::{
+    ConfigError, PathlikeOptions, WexeApp, get_config_file, read_config_file_with_profile, wexe_dbg,
+};
+use crate::console_colors::*;
+use crate::launch_retry::{RetryPolicy, StdinReplay};
+use crate::launch_timeout::{LaunchTimeout, TimeoutOutcome, format_duration};
+
+/// Errors that can occur when launching a wexe application
+#[derive(Debug)]
+pub enum LaunchError {
+    /// The configuration of the application could not be located or loaded
+    Config(ConfigError),
+    /// The target executable does not exist
+    TargetNotFound(PathBuf),
+    /// The new value of a PATH-like environment variable could not be built
+    /// (for instance because an element contains the path separator)
+    InvalidPathlike {
+        variable: String,
+        /// The element that cannot be joined
+        entry: PathBuf,
+        source: env::JoinPathsError,
+    },
+    /// An argument with the reserved prefix is not a known flag
+    UnknownFlag(String),
+    /// A timeout or grace period is not a valid duration. `setting` is the configuration
+    /// key or environment variable it came from.
+    InvalidDuration { setting: String, value: String },
+    /// Starting the target executable, or waiting for it, failed
+    Io(io::Error),
+}
+
+impl fmt::Display for LaunchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LaunchError::Config(e) => write!(f, "{e}"),
+            LaunchError::TargetNotFound(target) => {
+                write!(f, "Target executable does not exist: {}", target.to_string_lossy())
+            }
+            LaunchError::InvalidPathlike {
+                variable,
+                entry,
+                source,
+            } => write!(
+                f,
+                "Cannot build PATH-like variable {variable}: element {:?} is invalid ({source})",
+                entry.as_os_str()
+            ),
+            LaunchError::UnknownFlag(flag) => write!(f, "Unknown wexe flag: {flag}"),
+            LaunchError::InvalidDuration { setting, value } => {
+                write!(f, "Invalid duration for {setting}: '{value}'")
+            }
+            LaunchError::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for LaunchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LaunchError::Config(e) => Some(e),
+            LaunchError::InvalidPathlike { source, .. } => Some(source),
+            LaunchError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ConfigError> for LaunchError {
+    fn from(e: ConfigError) -> Self {
+        LaunchError::Config(e)
+    }
+}
+
+impl From<io::Error> for LaunchError {
+    fn from(e: io::Error) -> Self {
+        LaunchError::Io(e)
+    }
+}
+
+/// The reserved arguments controlling wexe itself, parsed out of the arguments of an
+/// application launch. With the default prefix (`--wexe-`) these are:
+/// * `--wexe-show`: describe the application instead of running it
+/// * `--wexe-dry-run`: print the command that would run instead of running it
+/// * `--wexe-debug`: turn on debug output, as `WEXE_DEBUG=1` does
+/// * `--wexe-profile=NAME`: select profile NAME (as does `+NAME` as first argument, if
+///   the application has a profile NAME)
+/// * `--wexe-no-args`: do not prepend or append the configured arguments
+/// * `--wexe--`: pass all following arguments to the target as they are
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LaunchFlags {
+    pub show: bool,
+    pub dry_run: bool,
+    pub debug: bool,
+    pub profile: Option<String>,
+    pub no_args: bool,
+}
+
+impl LaunchFlags {
+    /// Take the reserved arguments out of `args`, returning the flags they set and the
+    /// remaining arguments for the target. A first argument `+NAME` selects a profile if
+    /// NAME is one of the application's `profiles`; otherwise it is passed on (as in
+    /// `vim +10 file`). Arguments starting with `prefix` are reserved (unless `prefix`
+    /// is empty, which disables the flags); unknown ones are an error. Arguments that
+    /// are not valid Unicode are never reserved.
+    pub fn parse(
+        prefix: &str,
+        profiles: &[String],
+        args: Vec<OsString>,
+    ) -> Result<(LaunchFlags, Vec<OsString>), LaunchError> {
+        let mut flags = LaunchFlags::default();
+        let mut remaining: Vec<OsString> = Vec::new();
+        let mut args = args.into_iter().peekable();
+        let is_profile = |arg: &OsString| {
+            arg.to_str()
+                .and_then(|a| a.strip_prefix('+'))
+                .is_some_and(|name| profiles.iter().any(|p| p == name))
+        };
+        if let Some(arg) = args.next_if(is_profile) {
+            flags.profile = Some(arg.to_string_lossy()[1..].to_string());
+        }
+        while let Some(arg) = args.next() {
+            let flag = match arg.to_str().and_then(|arg| arg.strip_prefix(prefix)) {
+                Some(flag) if !prefix.is_empty() => flag,
+                _ => {
+                    remaining.push(arg);
+                    continue;
+                }
+            };
+            match flag {
+                "-" => {
+                    remaining.extend(args);
+                    break;
+                }
+                "show" => flags.show = true,
+                "dry-run" => flags.dry_run = true,
+                "debug" => flags.debug = true,
+                "no-args" => flags.no_args = true,
+                _ => match flag.strip_prefix("profile=") {
+                    Some(profile) if !profile.is_empty() => flags.profile = Some(profile.to_string()),
+                    _ => return Err(LaunchError::UnknownFlag(arg.to_string_lossy().to_string())),
+                },
+            }
+        }
+        Ok((flags, remaining))
+    }
+}
+
+/// Test whether two environment variable names are the same (ignoring case on Windows,
+/// where the names are case-insensitive).
+fn env_name_eq(a: &OsStr, b: &OsStr) -> bool {
+    if cfg!(windows) { a.eq_ignore_ascii_case(b) } else { a == b }
+}
+
+/// Normalize a path lexically (without looking at the file system): remove trailing
+/// separators and `.` elements, and resolve `..` elements where possible.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                // ".." of the root is the root
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                _ => normalized.push(".."),
+            },
+            component => normalized.push(component),
+        }
+    }
+    if normalized.as_os_str().is_empty() && !path.as_os_str().is_empty() {
+        normalized.push(".");
+    }
+    normalized
+}
+
+/// Clean up the elements of a PATH-like variable as its options ask for: remove the
+/// `remove` elements from the `inherited` ones, then normalize, drop the missing folders
+/// and the duplicates of all elements (in that order).
+fn clean_pathlike(
+    options: &PathlikeOptions,
+    prepend: Vec<PathBuf>,
+    inherited: Vec<PathBuf>,
+    append: Vec<PathBuf>,
+) -> Vec<PathBuf> {
+    let key = |path: &Path| {
+        if options.normalize {
+            normalize_path(path)
+        } else {
+            path.to_path_buf()
+        }
+    };
+    let remove: Vec<PathBuf> = options.remove.iter().map(|p| key(Path::new(p))).collect();
+    let inherited = inherited.into_iter().filter(|path| !remove.contains(&key(path)));
+    let mut elements: Vec<PathBuf> = Vec::new();
+    for path in prepend.into_iter().chain(inherited).chain(append) {
+        let path = key(&path);
+        if options.drop_missing && !path.is_dir() {
+            continue;
+        }
+        // Paths compare by component, so "/a/b/" equals "/a/b"
+        if options.dedupe && elements.contains(&path) {
+            continue;
+        }
+        elements.push(path);
+    }
+    elements
+}
+
+/// How a run of the target ended (see [Launcher::run_command])
+#[derive(Debug)]
+pub struct LaunchOutcome {
+    /// The process ID of the last attempt, if it was started
+    pub pid: Option<u32>,
+    /// The number of times the target was started
+    pub attempts: u32,
+    /// The exit status of the last attempt and whether its timeout stopped it, or the
+    /// error starting or waiting for it
+    pub result: io::Result<(ExitStatus, TimeoutOutcome)>,
+}
+
+/// Launches the target of a wexe application, applying its argument and environment
+/// changes, its timeout and its retry policy.
+pub struct Launcher {
+    app: WexeApp,
+    /// The compiled argument rewrite rules of the application
+    rewriter: ArgRewriter,
+    /// The compiled `[args.paths]` section of the application, if any
+    paths: Option<PathTranslator>,
+}
+
+impl Launcher {
+    /// Create a launcher for an application model.
+    pub fn new(app: WexeApp) -> Launcher {
+        let rewriter = ArgRewriter::new(&app.args_rewrite);
+        let paths = app.args_paths.as_ref().map(PathTranslator::new);
+        Launcher {
+            app,
+            rewriter,
+            paths,
+        }
+    }
+
+    /// Create a launcher for the application with the given tag, loading its
+    /// configuration file the same way a stub named after the tag would.
+    pub fn for_tag(tag: &str) -> Result<Launcher, LaunchError> {
+        Launcher::for_tag_with_profile(tag, None)
+    }
+
+    /// Like [Launcher::for_tag], selecting the given profile of the application (which
+    /// must exist) instead of the one the environment or configuration selects.
+    pub fn for_tag_with_profile(tag: &str, profile: Option<&str>) -> Result<Launcher, LaunchError> {
+        let cfg_file = get_config_file(tag.to_string())?;
+        let app = read_config_file_with_profile(cfg_file, profile)?;
+        Ok(Launcher::new(app))
+    }
+
+    /// Get the application model this launcher runs.
+    pub fn app(&self) -> &WexeApp {
+        &self.app
+    }
+
+    /// Apply the argument rewrite rules of the application to the given arguments.
+    /// With WEXE_DEBUG on, the changes each rule makes are traced.
+    pub fn rewrite_args(&self, args: Vec<OsString>) -> Vec<OsString> {
+        let (args, trace) = self.rewriter.apply(args);
+        if wexe_dbg() {
+            for change in trace {
+                eprintln!("{bg_B}Argument rewrite {fg_g}{change}{rst}.");
+            }
+        }
+        args
+    }
+
+    /// Translate the given arguments that are paths, as configured by the `[args.paths]`
+    /// section of the application (if any). Relative paths are resolved against the
+    /// current folder. With WEXE_DEBUG on, the translations are traced.
+    pub fn translate_paths(&self, args: Vec<OsString>) -> Vec<OsString> {
+        let Some(paths) = &self.paths else {
+            return args;
+        };
+        let Ok(cwd) = env::current_dir() else {
+            return args;
+        };
+        let (args, trace) = paths.translate(args, &cwd);
+        if wexe_dbg() {
+            for change in trace {
+                eprintln!("{bg_B}Argument {fg_g}{change}{rst}.");
+            }
+        }
+        args
+    }
+
+    /// Get the full argument list for the target: the configured arguments to prepend,
+    /// the given arguments (rewritten by [Launcher::rewrite_args], then translated by
+    /// [Launcher::translate_paths]), and the configured arguments to append.
+    pub fn effective_args(&self, args: Vec<OsString>) -> Vec<OsString> {
+        let args = self.rewrite_args(args);
+        let args = self.translate_paths(args);
+        let mut extended_args: Vec<OsString> = Vec::new();
+        extended_args.extend(self.app.args.prepend.iter().map(OsString::from));
+        extended_args.extend(args);
+        extended_args.extend(self.app.args.append.iter().map(OsString::from));
+        extended_args
+    }
+
+    /// Get the environment variables of the current process the target inherits: all of
+    /// them, or with `env.clear` only those matching one of the `env.keep` patterns.
+    /// Variables whose name is not valid Unicode never match a pattern.
+    pub fn inherited_env(&self) -> BTreeMap<OsString, OsString> {
+        let vars = env::vars_os();
+        if !self.app.env_clear {
+            return vars.collect();
+        }
+        // Environment variable names are case-insensitive on Windows
+        let options = glob::MatchOptions {
+            case_sensitive: !cfg!(windows),
+            ..Default::default()
+        };
+        let keep: Vec<glob::Pattern> = self
+            .app
+            .env_keep
+            .iter()
+            .filter_map(|pattern| glob::Pattern::new(pattern).ok())
+            .collect();
+        vars.filter(|(k, _)| {
+            k.to_str()
+                .is_some_and(|k| keep.iter().any(|pattern| pattern.matches_with(k, options)))
+        })
+        .collect()
+    }
+
+    /// Get the changes to the environment the target inherits (see
+    /// [Launcher::inherited_env]). Each change is the variable name and its new value, or
+    /// None if the variable is removed.
+    pub fn env_changes(&self) -> Result<BTreeMap<String, Option<OsString>>, LaunchError> {
+        let inherited = self.inherited_env();
+        let mut changes = BTreeMap::new();
+        for (k, v) in self.app.env_set.iter() {
+            // An empty value deletes the variable
+            let value = if v.is_empty() { None } else { Some(OsString::from(v)) };
+            changes.insert(k.clone(), value);
+        }
+        for (k, v) in self.app.env_pathlike.iter() {
+            let original = inherited.iter().find(|(name, _)| env_name_eq(name, OsStr::new(k)));
+            let originals: Vec<PathBuf> = match original {
+                Some((_, evar)) => env::split_paths(evar).collect(),
+                None => Vec::new(),
+            };
+            let options = self.app.env_pathlike_options.get(k).cloned().unwrap_or_default();
+            let new_elements = clean_pathlike(
+                &options,
+                v.prepend.iter().map(PathBuf::from).collect(),
+                originals,
+                v.append.iter().map(PathBuf::from).collect(),
+            );
+            let new_variable = env::join_paths(new_elements.iter()).map_err(|source| {
+                // Name the element that cannot be joined (it contains the separator)
+                let entry = new_elements.iter().find(|element| env::join_paths([element]).is_err());
+                LaunchError::InvalidPathlike {
+                    variable: k.clone(),
+                    entry: entry.cloned().unwrap_or_default(),
+                    source,
+                }
+            })?;
+            changes.insert(k.clone(), Some(new_variable));
+        }
+        Ok(changes)
+    }
+
+    /// Get the complete environment the target will run with: the inherited environment
+    /// (see [Launcher::inherited_env]) with the changes of [Launcher::env_changes] applied.
+    pub fn effective_env(&self) -> Result<BTreeMap<OsString, OsString>, LaunchError> {
+        let mut vars = self.inherited_env();
+        for (k, v) in self.env_changes()? {
+            // Replace the inherited variable, whatever the case of its name on Windows
+            vars.retain(|name, _| !env_name_eq(name, OsStr::new(&k)));
+            if let Some(v) = v {
+                vars.insert(k.into(), v);
+            }
+        }
+        Ok(vars)
+    }
+
+    /// Build the command that runs the target with the given arguments.
+    /// Fails if the target executable does not exist.
+    pub fn command(&self, args: Vec<OsString>) -> Result<Command, LaunchError> {
+        // Test if the target executable exists. In this usage that is an error.
+        // This cannot be tested earlier, because other usages may not require the target to exist.
+        let target = PathBuf::from(&self.app.target);
+        if !target.exists() {
+            return Err(LaunchError::TargetNotFound(target));
+        }
+        let mut cmd = Command::new(target);
+        cmd.args(self.effective_args(args));
+        if self.app.env_clear {
+            cmd.env_clear();
+            cmd.envs(self.inherited_env());
+        }
+        for (k, v) in self.env_changes()? {
+            match v {
+                Some(v) => cmd.env(k, v),
+                None => cmd.env_remove(k),
+            };
+        }
+        Ok(cmd)
+    }
+
+    /// Get the timeout of the target: the configured one, unless the `WEXE_TIMEOUT` and
+    /// `WEXE_KILL_AFTER` environment variables override it. None if there is none.
+    pub fn timeout(&self) -> Result<Option<LaunchTimeout>, LaunchError> {
+        LaunchTimeout::for_durations(self.app.timeout, self.app.kill_after)
+    }
+
+    /// Start the target with the given arguments, without waiting for it. Use
+    /// [Launcher::wait] to wait for it while applying its timeout.
+    pub fn spawn(&self, args: Vec<OsString>) -> Result<Child, LaunchError> {
+        Ok(self.command(args)?.spawn()?)
+    }
+
+    /// Wait for a target started by [Launcher::spawn], stopping it if it runs longer
+    /// than its timeout (see [LaunchTimeout::wait]).
+    pub fn wait(&self, child: &mut Child) -> Result<(ExitStatus, TimeoutOutcome), LaunchError> {
+        Ok(wait_with_timeout(self.timeout()?.as_ref(), child)?)
+    }
+
+    /// Get the retry policy of the target and how standard input is replayed for a retry.
+    /// None if the target is not retried, which includes standard input being a pipe
+    /// (a retried target must see the same input) unless the policy accepts that.
+    pub fn retry(&self) -> Option<(&RetryPolicy, StdinReplay)> {
+        let policy = self.app.retry.as_ref().filter(|policy| policy.attempts > 1)?;
+        let replay = match StdinReplay::detect() {
+            Some(replay) => replay,
+            None if policy.piped_stdin => StdinReplay::Nothing,
+            None => {
+                if wexe_dbg() {
+                    eprintln!(
+                        "{bg_B}Retries disabled: standard input is not a terminal and \
+                        cannot be replayed{rst}."
+                    );
+                }
+                return None;
+            }
+        };
+        Some((policy, replay))
+    }
+
+    /// Run a command built by [Launcher::command] and wait for it to finish, stopping it
+    /// if it runs longer than its timeout, and starting it again as its retry policy
+    /// asks. Only fails if the timeout is invalid; errors starting or waiting for the
+    /// target are part of the outcome.
+    pub fn run_command(&self, cmd: &mut Command) -> Result<LaunchOutcome, LaunchError> {
+        let timeout = self.timeout()?;
+        let mut retry = self.retry();
+        let mut attempts = 1;
+        loop {
+            let mut pid = None;
+            let result = cmd.spawn().and_then(|mut child| {
+                pid = Some(child.id());
+                wait_with_timeout(timeout.as_ref(), &mut child)
+            });
+            // A target stopped by its timeout is not retried
+            if let Some((policy, replay)) = &mut retry
+                && let Ok((status, TimeoutOutcome::Exited)) = &result
+                && attempts < policy.attempts
+                && policy.should_retry(status)
+            {
+                let delay = policy.delay_before(attempts);
+                if wexe_dbg() {
+                    eprintln!(
+                        "{bg_B}Attempt {fg_y}{attempts}{rst}{bg_B} of {fg_y}{}{rst}{bg_B} failed \
+                        ({fg_r}{status}{rst}{bg_B}); retrying in {fg_y}{}{rst}.",
+                        policy.attempts,
+                        format_duration(delay)
+                    );
+                }
+                thread::sleep(delay);
+                match replay.rewind() {
+                    Ok(()) => {
+                        attempts += 1;
+                        continue;
+                    }
+                    Err(e) => {
+                        eprintln!("{bg_B}Cannot rewind standard input for a retry: {fg_r}{e}{rst}.")
+                    }
+                }
+            }
+            return Ok(LaunchOutcome {
+                pid,
+                attempts,
+                result,
+            });
+        }
+    }
+
+    /// Run the target with the given arguments and wait for it to finish, stopping it
+    /// if it runs longer than its timeout and starting it again as its retry policy asks.
+    pub fn run(&self, args: Vec<OsString>) -> Result<ExitStatus, LaunchError> {
+        let outcome = self.run_command(&mut self.command(args)?)?;
+        Ok(outcome.result?.0)
+    }
+
+    /// Replace the current process by the target, run with the given arguments.
+    /// Only returns if that fails.
+    #[cfg(unix)]
+    pub fn exec(&self, args: Vec<OsString>) -> LaunchError {
+        use std::os::unix::process::CommandExt;
+        match self.command(args) {
+            Ok(mut cmd) => LaunchError::Io(cmd.exec()),
+            Err(e) => e,
+        }
+    }
+}
+
+/// Wait for a child process, applying `timeout` if there is one.
+fn wait_with_timeout(
+    timeout: Option<&LaunchTimeout>,
+    child: &mut Child,
+) -> io::Result<(ExitStatus, TimeoutOutcome)> {
+    match timeout {
+        Some(timeout) => timeout.wait(child),
+        None => child.wait().map(|status| (status, TimeoutOutcome::Exited)),
+    }
+}
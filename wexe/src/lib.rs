@@ -1,2 +1,8 @@
+pub mod args_rewrite;
 pub mod config_model;
 pub mod console_colors;
+pub mod launch_log;
+pub mod launch_retry;
+pub mod launch_timeout;
+pub mod launcher;
+pub mod usage_stats;
@@ -1,67 +1,133 @@
 use std::env;
-use std::process::Command;
-use std::process::Termination;
-use std::{error::Error, path::PathBuf};
+use std::ffi::OsString;
+use std::time::Instant;
+use std::{error::Error, path::Path};
 // use std::os::windows::process::ExitCodeExt; // not yet stable :(
-use ctrlc;
 
-use wexe::config_model::{WexeApp, get_config_file, is_valid_app_tag, read_config_file, wexe_dbg};
+use wexe::config_model::{
+    ConfigError, DEFAULT_WEXE_FLAG_PREFIX, ListOps, enable_wexe_dbg, get_config_file,
+    is_valid_app_tag, read_app_config, read_wexe_settings, resolve_app_config, wexe_dbg,
+};
 use wexe::console_colors::*;
+use wexe::launch_log::{LaunchLog, LaunchRecord};
+use wexe::launch_timeout::{TIMEOUT_EXIT_CODE, TimeoutOutcome};
+use wexe::usage_stats::{get_wexe_stats_file, record_launch};
+use wexe::launcher::{LaunchError, LaunchFlags, Launcher};
 
-fn run_app_raw(args: Vec<String>, cfg: WexeApp) -> Result<i32, Box<dyn Error>> {
-    let mut extended_args: Vec<String> = Vec::new();
-    extended_args.extend(cfg.args.prepend);
-    extended_args.extend(args);
-    extended_args.extend(cfg.args.append);
+/// Describe the application being launched (for the `show` launch flag).
+fn show_app(
+    tag: &str,
+    cfg_file: &Path,
+    profile: Option<&str>,
+    launcher: &Launcher,
+    args: &[OsString],
+) {
+    let app = launcher.app();
+    println!("{fg_W}{:<14}{rst} {fg_y}{tag}{rst}", "Application");
+    println!("{fg_W}{:<14}{rst} {fg_c}{}{rst}", "Config file", cfg_file.to_string_lossy());
+    println!("{fg_W}{:<14}{rst} {}", "Profile", profile.unwrap_or("(none)"));
+    println!("{fg_W}{:<14}{rst} {fg_g}{}{rst}", "Target", app.target);
+    println!("{fg_W}{:<14}{rst} {:?}", "Args prepend", app.args.prepend);
+    println!("{fg_W}{:<14}{rst} {:?}", "Args given", args);
+    println!("{fg_W}{:<14}{rst} {:?}", "Args append", app.args.append);
+}
 
-    // Test if the target executable exists. In this usage that is an error.
-    // This cannot be tested earlier, because other usages may not require the target to exist.
-    let target = PathBuf::from(&cfg.target);
-    if !target.exists() {
-        eprintln!(
-            "{bg_B}Target executable does not exist: {fg_r}{:}{rst}.",
-            cfg.target
-        );
-        let error_text = format!("Target executable does not exist: {:}", cfg.target);
-        let error = std::io::Error::new(std::io::ErrorKind::NotFound, error_text);
-        return Err(Box::new(error));
-    }
-
-    let mut cmd = Command::new(cfg.target);
-    cmd.args(extended_args);
-    for (k, v) in cfg.env_set.iter() {
-        if v.is_empty() {
-            // eprintln!("{bg_B}Removing env variable {fg_o}{:}{rst}.", k);
-            cmd.env_remove(k);
-        } else {
-            // eprintln!("{bg_B}Setting env variable {fg_o}{:}{rst} to {fg_g}{:?}{rst}.", k, v);
-            cmd.env(k, v);
+/// Print the command that would run (for the `dry-run` launch flag).
+fn print_dry_run(launcher: &Launcher, args: Vec<OsString>) -> Result<(), LaunchError> {
+    let changes = launcher.env_changes()?;
+    let mut command = vec![OsString::from(&launcher.app().target)];
+    command.extend(launcher.effective_args(args));
+    println!("{fg_W}Would run{rst}: {fg_g}{:?}{rst}", command);
+    if launcher.app().env_clear {
+        let kept: Vec<OsString> = launcher.inherited_env().into_keys().collect();
+        println!("  {fg_o}(environment cleared){rst}, keeping {fg_y}{:?}{rst}", kept);
+    }
+    for (name, value) in changes {
+        match value {
+            Some(value) => println!("  {fg_y}{name}{rst}={fg_c}{}{rst}", value.to_string_lossy()),
+            None => println!("  {fg_y}{name}{rst} {fg_o}(deleted){rst}"),
         }
     }
-    for (k, v) in cfg.env_pathlike.iter() {
-        let originals: Vec<PathBuf> = {
-            let evar = env::var(k);
-            match evar {
-                Ok(evar) => env::split_paths(evar.as_str()).collect(),
-                Err(_) => Vec::new(),
-            }
-        };
-        let mut new_elements: Vec<PathBuf> = Vec::new();
-        new_elements.extend(v.prepend.iter().map(|s| PathBuf::from(s)));
-        new_elements.extend(originals.iter().map(|s| s.clone()));
-        new_elements.extend(v.append.iter().map(|s| PathBuf::from(s)));
+    Ok(())
+}
+
+/// Append the record of a launch to the launch log, if there is one. Failing to write
+/// the log does not fail the launch.
+fn write_launch_log(
+    log: Option<(LaunchLog, LaunchRecord)>,
+    update: impl FnOnce(&mut LaunchRecord),
+) {
+    let Some((log, mut record)) = log else {
+        return;
+    };
+    update(&mut record);
+    if let Err(e) = log.append(&record) {
+        let path = log.path.to_string_lossy();
+        eprintln!("{bg_B}Cannot write the launch log {fg_r}{path}{rst}: {e}.");
+    }
+}
 
-        let new_variable: String = env::join_paths(new_elements.iter())
-            .unwrap()
-            .into_string()
-            .unwrap();
-        // eprintln!("{bg_B}Edited PATH-like variable {fg_o}{:}{rst}: {fg_g}{:?}{rst}.", k, &new_variable);
-        cmd.env(k, new_variable);
+/// Count a launch of the application `tag` in the usage statistics, if they are enabled
+/// (`tag` is None if not). Failing to update the statistics does not fail the launch.
+fn record_usage(
+    tag: Option<&str>,
+    started: chrono::DateTime<chrono::Utc>,
+    runtime_ms: u64,
+    success: bool,
+) {
+    let Some(tag) = tag else {
+        return;
+    };
+    let stats_file = get_wexe_stats_file();
+    if let Err(e) = record_launch(&stats_file, tag, started, runtime_ms, success) {
+        let path = stats_file.to_string_lossy();
+        eprintln!("{bg_B}Cannot update the usage statistics {fg_r}{path}{rst}: {e}.");
     }
+}
+
+fn run_app_raw(
+    args: Vec<OsString>,
+    launcher: Launcher,
+    log: Option<(LaunchLog, LaunchRecord)>,
+    stats_tag: Option<&str>,
+) -> Result<i32, Box<dyn Error>> {
+    let start = Instant::now();
+    let mut cmd = match launcher.command(args) {
+        Ok(cmd) => cmd,
+        Err(LaunchError::TargetNotFound(target)) => {
+            eprintln!(
+                "{bg_B}Target executable does not exist: {fg_r}{:}{rst}.",
+                target.to_string_lossy()
+            );
+            let error = LaunchError::TargetNotFound(target);
+            write_launch_log(log, |record| record.error = Some(error.to_string()));
+            return Err(Box::new(error));
+        }
+        Err(e) => {
+            write_launch_log(log, |record| record.error = Some(e.to_string()));
+            return Err(Box::new(e));
+        }
+    };
 
     if wexe_dbg() {
         eprintln!("{bg_B}Running command: {fg_g}{:?}{rst}.", cmd);
     }
+    let log = log.map(|(log, mut record)| {
+        record.timestamp = chrono::Local::now().to_rfc3339();
+        record.argv = std::iter::once(cmd.get_program())
+            .chain(cmd.get_args())
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+        record.env = launcher
+            .env_changes()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(k, v)| (k, v.map(|v| v.to_string_lossy().to_string())))
+            .collect();
+        record.env_cleared = launcher.app().env_clear;
+        record.cwd = env::current_dir().unwrap_or_default().to_string_lossy().to_string();
+        (log, record)
+    });
 
     // About to actually run the command. Disable CTRL-C handling here, so the target process
     // decides how to handle it. If we would not do this, the target process would do its own
@@ -72,9 +138,42 @@ fn run_app_raw(args: Vec<String>, cfg: WexeApp) -> Result<i32, Box<dyn Error>> {
     })
     .expect("Error setting Ctrl-C handler");
 
-    let status = cmd.status();
-    match status {
-        Ok(status) => {
+    let started = chrono::Utc::now();
+    let outcome = match launcher.run_command(&mut cmd) {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            write_launch_log(log, |record| record.error = Some(e.to_string()));
+            return Err(Box::new(e));
+        }
+    };
+    let duration_ms = start.elapsed().as_millis() as u64;
+    let (pid, attempts) = (outcome.pid, outcome.attempts);
+    match outcome.result {
+        Ok((status, outcome)) => {
+            let timed_out = outcome != TimeoutOutcome::Exited;
+            record_usage(stats_tag, started, duration_ms, status.success() && !timed_out);
+            write_launch_log(log, |record| {
+                record.pid = pid;
+                record.duration_ms = duration_ms;
+                record.exit_code = status.code();
+                record.timed_out = timed_out;
+                record.attempts = attempts;
+                #[cfg(unix)]
+                {
+                    use std::os::unix::process::ExitStatusExt;
+                    record.signal = status.signal();
+                }
+            });
+            if timed_out {
+                if wexe_dbg() {
+                    eprintln!(
+                        "{bg_B}Command timed out ({fg_o}{:?}{rst}{bg_B}); exiting with code \
+                        {fg_r}{TIMEOUT_EXIT_CODE}{rst}.",
+                        outcome
+                    );
+                }
+                return Ok(TIMEOUT_EXIT_CODE);
+            }
             if status.success() {
                 if wexe_dbg() {
                     eprintln!("{bg_B}Command succeeded with exit code: {fg_g}0{rst}.");
@@ -94,8 +193,12 @@ fn run_app_raw(args: Vec<String>, cfg: WexeApp) -> Result<i32, Box<dyn Error>> {
             Ok(status.code().unwrap_or(0))
         }
         Err(e) => {
+            write_launch_log(log, |record| {
+                record.pid = pid;
+                record.error = Some(e.to_string());
+            });
             println!("{rst}Command failed with error: {fg_r}{:?}{rst}.", e);
-            Err(Box::new(e))
+            Err(Box::new(LaunchError::Io(e)))
         }
     }
 }
@@ -114,79 +217,113 @@ fn run_app(tag: String, skip1: bool) -> Result<i32, Box<dyn Error>> {
     }
 
     let skip_count = if skip1 { 2 } else { 1 };
-    let args: Vec<String> = env::args().skip(skip_count).collect();
+    // Arguments are passed on as they are, even if they are not valid Unicode
+    let args: Vec<OsString> = env::args_os().skip(skip_count).collect();
 
-    let cfg: WexeApp = {
-        if &tag == "wexecfg" {
-            // Use hard-coded configuration for wexecfg. wexe itself also gets
-            // redirected here, unless its first argument is a valid app name.
-            let cfg = wexe::config_model::wexecfg_config_file();
+    if &tag == "wexecfg" {
+        // Use hard-coded configuration for wexecfg. wexe itself also gets
+        // redirected here, unless its first argument is a valid app name.
+        let cfg = wexe::config_model::wexecfg_config_file().map_err(LaunchError::Config)?;
+        if wexe_dbg() {
+            eprintln!(
+                "{bg_B}Using hardcoded config for app {fg_o}wexecfg{rst}{bg_B}: {fg_g}{:?}{rst}.",
+                cfg
+            );
+        }
+        return run_app_raw(args, Launcher::new(cfg), None, None);
+    }
+
+    let cfg_file = match get_config_file(tag.clone()) {
+        Ok(cfg_file) => {
             if wexe_dbg() {
                 eprintln!(
-                    "{bg_B}Using hardcoded config for app {fg_o}wexecfg{rst}{bg_B}: {fg_g}{:?}{rst}.",
-                    cfg
+                    "{bg_B}Config file for app {fg_o}{:}{rst}{bg_B}: {fg_g}{:}{rst}.",
+                    tag.clone(),
+                    cfg_file.to_string_lossy()
                 );
             }
-            cfg
-        } else {
-            if !is_valid_app_tag(&tag) {
-                eprintln!(
+            cfg_file
+        }
+        Err(e) => {
+            match &e {
+                ConfigError::InvalidTag(_) => eprintln!(
                     "{bg_B}Invalid application tag '{fg_r}{:}{rst}{bg_B}'{rst}.",
                     tag.clone()
-                );
-                let error_text = format!("Invalid app tag '{}'.", tag.clone());
-                let error = std::io::Error::new(std::io::ErrorKind::InvalidInput, error_text);
-                return Err(Box::new(error));
-            }
-            let cfg_file_opt = get_config_file(tag.clone());
-            let cfg_file = match cfg_file_opt {
-                Some(cfg_file) => {
-                    if wexe_dbg() {
-                        eprintln!(
-                            "{bg_B}Config file for app {fg_o}{:}{rst}{bg_B}: {fg_g}{:}{rst}.",
-                            tag.clone(),
-                            cfg_file.to_string_lossy()
-                        );
-                    }
-                    cfg_file
-                }
-                None => {
-                    eprintln!(
-                        "{bg_B}No config file found for app '{fg_r}{:}{rst}{bg_B}'{rst}.",
-                        tag.clone()
-                    );
-                    let error_text = format!("No configuration file for '{}'.", tag.clone());
-                    let error = std::io::Error::new(std::io::ErrorKind::NotFound, error_text);
-                    return Err(Box::new(error));
-                }
-            };
-
-            let cfg = read_config_file(cfg_file)?;
-            if wexe_dbg() {
-                println!(
-                    "{bg_B}Config for app {fg_o}{:}{rst}{bg_B}: {fg_g}{:?}{rst}.",
-                    tag.clone(),
-                    cfg
-                );
+                ),
+                ConfigError::NotFound(_) => eprintln!(
+                    "{bg_B}No config file found for app '{fg_r}{:}{rst}{bg_B}'{rst}.",
+                    tag.clone()
+                ),
+                _ => {}
             }
-            cfg
+            return Err(Box::new(LaunchError::Config(e)));
         }
     };
 
-    run_app_raw(args, cfg)
+    // Take out the arguments reserved for wexe itself. Their prefix is configurable,
+    // so they can only be parsed once the configuration has been read.
+    let raw_cfg = read_app_config(&cfg_file).map_err(LaunchError::Config)?;
+    let prefix = raw_cfg
+        .wexe_flag_prefix
+        .clone()
+        .unwrap_or(DEFAULT_WEXE_FLAG_PREFIX.to_string());
+    let (flags, args) = LaunchFlags::parse(&prefix, &raw_cfg.profile_names(), args)?;
+    if flags.debug {
+        enable_wexe_dbg();
+    }
+    if wexe_dbg() {
+        eprintln!("{bg_B}Launch flags: {fg_g}{:?}{rst}.", flags);
+    }
+    let profile = raw_cfg.select_profile(flags.profile.as_deref()).ok().flatten();
+    // A settings file that cannot be read only disables the global launch log and the
+    // usage statistics
+    let settings = read_wexe_settings().unwrap_or_default();
+    let stats_tag = settings.stats.unwrap_or(false).then(|| tag.clone());
+    let log = LaunchLog::for_app(raw_cfg.log.as_deref(), &settings).map(|log| {
+        let record = LaunchRecord {
+            tag: tag.clone(),
+            config: cfg_file.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        (log, record)
+    });
+    let mut cfg = resolve_app_config(cfg_file.clone(), raw_cfg, flags.profile.as_deref())
+        .map_err(LaunchError::Config)?;
+    if flags.no_args {
+        cfg.args = ListOps::default();
+    }
+    if wexe_dbg() {
+        eprintln!(
+            "{bg_B}Config for app {fg_o}{:}{rst}{bg_B}: {fg_g}{:?}{rst}.",
+            tag.clone(),
+            cfg
+        );
+    }
+
+    let launcher = Launcher::new(cfg);
+    if flags.show || flags.dry_run {
+        if flags.show {
+            show_app(&tag, &cfg_file, profile.as_deref(), &launcher, &args);
+        }
+        if flags.dry_run {
+            print_dry_run(&launcher, args)?;
+        }
+        return Ok(0);
+    }
+    run_app_raw(args, launcher, log, stats_tag.as_deref())
 }
 
 fn run_wexe() -> Result<i32, Box<dyn Error>> {
-    let first_arg = env::args().nth(1);
-    match first_arg {
-        Some(tag) => {
-            if !tag.starts_with("-") && !tag.starts_with("/") && !tag.starts_with("+") {
-                // alternative redirect mode syntax
-                return run_app(tag, true);
-            }
-        }
-        None => (),
-    };
+    // An application tag is always valid Unicode
+    let first_arg = env::args_os().nth(1).and_then(|arg| arg.into_string().ok());
+    if let Some(tag) = first_arg
+        && !tag.starts_with("-")
+        && !tag.starts_with("/")
+        && !tag.starts_with("+")
+    {
+        // alternative redirect mode syntax
+        return run_app(tag, true);
+    }
     if wexe_dbg() {
         eprintln!("{bg_B}No app specified: redirecting to {fg_o}wexecfg{rst}.");
     }
@@ -195,7 +332,22 @@ fn run_wexe() -> Result<i32, Box<dyn Error>> {
 
 fn mainmain() -> Result<i32, Box<dyn Error>> {
     let exe = env::current_exe()?;
-    let tag = exe.file_stem().unwrap().to_str().unwrap().to_lowercase();
+    let mut tag = match exe.file_stem() {
+        Some(stem) => stem.to_string_lossy().to_lowercase(),
+        None => return Err(format!("Invalid executable path: {}", exe.display()).into()),
+    };
+    if tag == "wexe" {
+        // The executable may be a symbolic link to wexe, in which case current_exe() has
+        // resolved the link. The name it was invoked by is still available in argv[0].
+        if let Some(arg0) = env::args_os().next()
+            && let Some(stem) = Path::new(&arg0).file_stem()
+        {
+            let invoked_as = stem.to_string_lossy().to_lowercase();
+            if is_valid_app_tag(&invoked_as) {
+                tag = invoked_as;
+            }
+        }
+    }
 
     if tag == "wexe" {
         // the original application name (not renamed)
@@ -217,11 +369,10 @@ fn main() {
         }
         Err(e) => {
             // eprintln!("{bg_B}{fg_r}Failed.{rst}");
-            let result2: Result<(), Box<dyn Error>> = Err(e);
             // Lets not colorize the report, avoiding the risk of not reseting the colors.
-            // eprint!("{bg_B}");
-            let _exitcode = result2.report();
-            // eprint!("{rst}");
+            // (Report the error message rather than its Debug form, which is what
+            // Termination::report() would print)
+            eprintln!("Error: {e}");
             std::process::exit(1);
         }
     }
@@ -0,0 +1,120 @@
+//! Usage statistics of wexe applications: the number of launches, the last time each
+//! application was used, its cumulative runtime and how often it exited with a non-zero
+//! code. The statistics of all applications share one JSON file in the wexe configuration
+//! folder, which launches update under an exclusive file lock, so concurrent stubs do not
+//! lose each other's updates.
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config_model::get_wexe_cfg_dir;
+
+/// The name of the usage statistics file in the wexe configuration directory
+pub const WEXE_STATS_FILE_NAME: &str = "wexe.stats.json";
+
+/// Get the path to the usage statistics file in the wexe configuration directory.
+/// (the resulting path may or may not exist as a file)
+pub fn get_wexe_stats_file() -> PathBuf {
+    get_wexe_cfg_dir().join(WEXE_STATS_FILE_NAME)
+}
+
+/// The usage statistics of one application
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppStats {
+    /// The number of times the target was started
+    pub launches: u64,
+    /// When the target was last started (RFC 3339, UTC)
+    pub last_used: Option<String>,
+    /// The cumulative runtime of the target, in milliseconds
+    pub runtime_ms: u64,
+    /// The number of times the target did not exit with code 0
+    pub nonzero_exits: u64,
+}
+
+impl AppStats {
+    /// When the target was last started, if it ever was (and the time is readable)
+    pub fn last_used_time(&self) -> Option<DateTime<Utc>> {
+        let last_used = self.last_used.as_deref()?;
+        DateTime::parse_from_rfc3339(last_used)
+            .ok()
+            .map(|time| time.with_timezone(&Utc))
+    }
+
+    /// The number of whole days since the target was last started, if it ever was
+    pub fn days_unused(&self, now: DateTime<Utc>) -> Option<i64> {
+        self.last_used_time().map(|time| (now - time).num_days())
+    }
+}
+
+/// The usage statistics of all applications, by tag
+pub type UsageStats = BTreeMap<String, AppStats>;
+
+/// Parse the contents of a statistics file. An empty file has no statistics.
+fn parse_stats(file: &mut File) -> io::Result<UsageStats> {
+    let mut text = String::new();
+    file.read_to_string(&mut text)?;
+    if text.trim().is_empty() {
+        return Ok(UsageStats::new());
+    }
+    serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Read a usage statistics file, returning no statistics if it does not exist.
+/// The file is read under a shared lock, so a concurrent update is never seen halfway.
+pub fn read_usage_stats(stats_file: &Path) -> io::Result<UsageStats> {
+    let mut file = match File::open(stats_file) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(UsageStats::new()),
+        Err(e) => return Err(e),
+    };
+    file.lock_shared()?;
+    parse_stats(&mut file)
+}
+
+/// Record a launch of the application `tag` in a usage statistics file: the target was
+/// started at `started`, ran for `runtime_ms` milliseconds and exited with code 0 or not
+/// (`success`). The file is created if needed, and updated under an exclusive lock.
+/// A file that cannot be parsed (for instance after a crash while writing it) is left
+/// alone, so its history is not lost: the launch is not recorded and an
+/// [io::ErrorKind::InvalidData] error asks for the file to be fixed or removed.
+pub fn record_launch(
+    stats_file: &Path,
+    tag: &str,
+    started: DateTime<Utc>,
+    runtime_ms: u64,
+    success: bool,
+) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(stats_file)?;
+    file.lock()?;
+    let mut stats = parse_stats(&mut file).map_err(|e| match e.kind() {
+        io::ErrorKind::InvalidData => io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("the file is corrupt ({e}); fix or remove it to record launches again"),
+        ),
+        _ => e,
+    })?;
+    let app_stats = stats.entry(tag.to_string()).or_default();
+    app_stats.launches += 1;
+    app_stats.last_used = Some(started.to_rfc3339());
+    app_stats.runtime_ms += runtime_ms;
+    if !success {
+        app_stats.nonzero_exits += 1;
+    }
+    let text = serde_json::to_string_pretty(&stats).map_err(io::Error::other)?;
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(text.as_bytes())?;
+    file.write_all(b"\n")
+    // The lock is released when the file is closed
+}
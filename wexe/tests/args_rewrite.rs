@@ -0,0 +1,166 @@
+//! Tests for the `[[args.rewrite]]` argument rewriting rules and the `[args.paths]`
+//! path argument translation.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+
+use wexe::args_rewrite::{ArgRewriter, PathTranslator, RewriteRule};
+use wexe::config_model::{ConfigArgPaths, ConfigRewriteRule, WexeApp, WexeAppConfig};
+
+fn strings(args: &[&str]) -> Vec<OsString> {
+    args.iter().map(OsString::from).collect()
+}
+
+fn rules(text: &str) -> Vec<ConfigRewriteRule> {
+    let cfg: WexeAppConfig = toml::from_str(text).unwrap();
+    WexeApp::from_config(cfg).args_rewrite
+}
+
+const RULES: &str = r#"
+target = "/bin/tool"
+
+[[args.rewrite]]
+match = '^/(\w+)$'
+replace = '--$1'
+
+[[args.rewrite]]
+match = '^--colour(=.*)?$'
+replace = '--color$1'
+
+[[args.rewrite]]
+match = '^--quiet$'
+drop = true
+
+[[args.rewrite]]
+insert = "--color=auto"
+unless = '^--color'
+"#;
+
+#[test]
+fn rules_are_applied_in_order() {
+    let (args, trace) = ArgRewriter::new(&rules(RULES)).apply(strings(&["/verbose", "file", "--quiet", "/a/b"]));
+    assert_eq!(args, vec!["--color=auto", "--verbose", "file", "/a/b"]);
+    assert_eq!(
+        trace,
+        vec![
+            "rule 1: replaced \"/verbose\" by \"--verbose\"",
+            "rule 3: dropped \"--quiet\"",
+            "rule 4: inserted \"--color=auto\"",
+        ]
+    );
+
+    // A renamed option counts for the insert rule that follows it
+    let (args, _) = ArgRewriter::new(&rules(RULES)).apply(strings(&["--colour=never"]));
+    assert_eq!(args, vec!["--color=never"]);
+}
+
+#[test]
+fn insert_defaults_to_checking_for_the_argument_itself() {
+    let rules = rules("target = '/bin/tool'\n[[args.rewrite]]\ninsert = '-x'\n");
+    assert_eq!(ArgRewriter::new(&rules).apply(strings(&["a", "-x"])).0, vec!["a", "-x"]);
+    assert_eq!(ArgRewriter::new(&rules).apply(strings(&["a", "-xy"])).0, vec!["-x", "a", "-xy"]);
+}
+
+#[test]
+fn invalid_rules_are_rejected() {
+    let invalid = [
+        ConfigRewriteRule {
+            pattern: Some("(".into()),
+            replace: Some("x".into()),
+            ..Default::default()
+        },
+        ConfigRewriteRule {
+            pattern: Some("x".into()),
+            ..Default::default()
+        },
+        ConfigRewriteRule {
+            pattern: Some("x".into()),
+            drop: Some(true),
+            insert: Some("y".into()),
+            ..Default::default()
+        },
+    ];
+    for rule in invalid.iter() {
+        assert!(RewriteRule::compile(rule).is_err(), "{rule:?}");
+    }
+    let (args, trace) = ArgRewriter::new(&invalid).apply(strings(&["x"]));
+    assert_eq!(args, vec!["x"]);
+    assert_eq!(trace.len(), 3);
+}
+
+fn arg_paths(text: &str) -> ConfigArgPaths {
+    let cfg: WexeAppConfig = toml::from_str(text).unwrap();
+    WexeApp::from_config(cfg).args_paths.unwrap()
+}
+
+#[test]
+fn existing_and_matching_paths_are_absolutized() {
+    let cwd = env::temp_dir().join(format!("wexe-arg-paths-{}", std::process::id()));
+    fs::create_dir_all(cwd.join("sub")).unwrap();
+    fs::write(cwd.join("sub").join("file.txt"), "").unwrap();
+    let paths =
+        arg_paths("target = '/bin/tool'\n[args.paths]\nexisting = true\nmatch = ['\\.log$']\n");
+    let (args, trace) = PathTranslator::new(&paths).translate(
+        strings(&["sub/file.txt", "--flag", "new.log", "missing.txt", ""]),
+        &cwd,
+    );
+    let expected = |name: &str| cwd.join(name).into_os_string();
+    assert_eq!(
+        args,
+        vec![
+            expected("sub/file.txt"),
+            "--flag".into(),
+            expected("new.log"),
+            "missing.txt".into(),
+            "".into()
+        ]
+    );
+    assert_eq!(trace.len(), 2);
+
+    // Without absolute and existing, only matching paths are mapped
+    let paths = arg_paths(
+        r#"
+target = "/bin/tool"
+[args.paths]
+absolute = false
+existing = false
+match = ['^/mnt/']
+[[args.paths.map]]
+from = "/mnt/c/"
+to = 'C:\'
+separator = '\'
+[[args.paths.map]]
+from = "/mnt/"
+to = "//server/"
+"#,
+    );
+    let (args, _) =
+        PathTranslator::new(&paths).translate(strings(&["sub", "/mnt/c/a/b", "/mnt/d/e"]), &cwd);
+    assert_eq!(args, vec!["sub", r"C:\a\b", "//server/d/e"]);
+
+    // Arguments naming existing files are only paths with existing = true
+    let paths = arg_paths("target = '/bin/tool'\n[args.paths]\nmatch = ['\\.log$']\n");
+    let (args, _) = PathTranslator::new(&paths).translate(strings(&["sub", "new.log"]), &cwd);
+    assert_eq!(args, vec!["sub".into(), expected("new.log")]);
+    fs::remove_dir_all(&cwd).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn arguments_that_are_not_unicode_are_left_alone() {
+    use std::os::unix::ffi::OsStringExt;
+
+    let invalid = OsString::from_vec(b"/\xff".to_vec());
+    let rules = rules(
+        "target = '/bin/tool'\n[[args.rewrite]]\nmatch = '^/'\nreplace = '-'\n\
+        [[args.rewrite]]\nmatch = '.'\ndrop = true\n",
+    );
+    let (args, trace) = ArgRewriter::new(&rules).apply(vec![invalid.clone(), "/x".into()]);
+    assert_eq!(args, vec![invalid.clone()]);
+    assert_eq!(trace.len(), 2);
+
+    let paths = arg_paths("target = '/bin/tool'\n[args.paths]\nmatch = ['^/']\n");
+    let (args, _) = PathTranslator::new(&paths).translate(vec![invalid.clone()], &env::temp_dir());
+    assert_eq!(args, vec![invalid]);
+}
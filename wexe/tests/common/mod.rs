@@ -0,0 +1,189 @@
+//! Shared harness for the integration tests: a sandboxed wexe repository in a temporary
+//! folder, driven through the real `wexe` and `wexecfg` executables.
+
+#![allow(dead_code)] // not every test file uses every helper
+
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use serde_json::Value;
+
+/// A temporary wexe installation. The wexe configuration folder is redirected into the
+/// sandbox through the environment of every process started from it. The sandbox is
+/// removed when dropped.
+pub struct Sandbox {
+    root: PathBuf,
+}
+
+impl Sandbox {
+    /// Create an empty sandbox. `name` must be unique among the tests.
+    pub fn new(name: &str) -> Sandbox {
+        let root = std::env::temp_dir().join(format!("wexe-it-{}-{name}", std::process::id()));
+        if root.exists() {
+            fs::remove_dir_all(&root).unwrap();
+        }
+        fs::create_dir_all(root.join("config")).unwrap();
+        fs::create_dir_all(root.join("tools")).unwrap();
+        Sandbox { root }
+    }
+
+    /// Create a sandbox with wexe and wexecfg installed and the echoargs helper
+    /// available in its tools folder.
+    pub fn installed(name: &str) -> Sandbox {
+        let sandbox = Sandbox::new(name);
+        let output = sandbox.wexecfg(&["/install", "-wexe"]);
+        assert!(output.status.success(), "install failed: {}", stderr(&output));
+        fs::copy(echoargs_exe(), sandbox.echoargs()).unwrap();
+        sandbox
+    }
+
+    /// The folder containing the sandbox
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The wexe configuration folder (where configurations and stubs live)
+    pub fn wexe_home(&self) -> PathBuf {
+        #[cfg(target_os = "macos")]
+        return self.root.join("Library/Application Support/.wexe");
+        #[cfg(not(target_os = "macos"))]
+        return self.root.join("config").join(".wexe");
+    }
+
+    /// The sandbox copy of the echoargs helper
+    pub fn echoargs(&self) -> PathBuf {
+        self.root.join("tools").join(exe_name("echoargs"))
+    }
+
+    /// The path to the stub for an application
+    pub fn stub(&self, tag: &str) -> PathBuf {
+        self.wexe_home().join(exe_name(tag))
+    }
+
+    /// The path to the configuration file for an application
+    pub fn config(&self, tag: &str) -> PathBuf {
+        self.wexe_home().join(format!("{tag}.toml"))
+    }
+
+    /// Write the configuration file for an application. Occurrences of `{echoargs}`
+    /// in `text` are replaced by the path to the echoargs helper.
+    pub fn write_config(&self, tag: &str, text: &str) {
+        let echoargs = self.echoargs().to_string_lossy().replace('\\', "\\\\");
+        fs::write(self.config(tag), text.replace("{echoargs}", &echoargs)).unwrap();
+    }
+
+    /// Write the configuration file for an application (see [Sandbox::write_config])
+    /// and create its stub.
+    pub fn add_app(&self, tag: &str, text: &str) {
+        self.write_config(tag, text);
+        let output = self.wexecfg(&["/fix", tag]);
+        assert!(output.status.success(), "fix failed: {}", stderr(&output));
+        assert!(self.stub(tag).exists(), "no stub created for {tag}");
+    }
+
+    /// Apply the sandbox environment to a command.
+    pub fn sandboxed(&self, command: &mut Command) {
+        command
+            .env("HOME", &self.root)
+            .env("XDG_CONFIG_HOME", self.root.join("config"))
+            .env("WEXE_DEBUG", "0")
+            .current_dir(&self.root);
+    }
+
+    /// Run wexecfg with the given arguments.
+    pub fn wexecfg(&self, args: &[&str]) -> Output {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_wexecfg"));
+        command.args(args);
+        self.sandboxed(&mut command);
+        command.output().unwrap()
+    }
+
+    /// Run an application stub with the given arguments and extra environment variables.
+    pub fn run_stub(&self, tag: &str, args: &[&str], envs: &[(&str, &str)]) -> Output {
+        let args: Vec<&OsStr> = args.iter().map(OsStr::new).collect();
+        let envs: Vec<(&str, &OsStr)> = envs.iter().map(|(k, v)| (*k, OsStr::new(v))).collect();
+        self.run_stub_os(tag, &args, &envs)
+    }
+
+    /// Like [Sandbox::run_stub], with arguments and values that need not be valid Unicode.
+    pub fn run_stub_os(&self, tag: &str, args: &[&OsStr], envs: &[(&str, &OsStr)]) -> Output {
+        let mut command = Command::new(self.stub(tag));
+        command.args(args);
+        self.sandboxed(&mut command);
+        command.envs(envs.iter().copied());
+        command.output().unwrap()
+    }
+
+    /// Run an application stub wrapping echoargs, expecting it to succeed, and return
+    /// the report echoargs printed.
+    pub fn run_echo(&self, tag: &str, args: &[&str], envs: &[(&str, &str)]) -> Value {
+        let output = self.run_stub(tag, args, envs);
+        assert!(output.status.success(), "stub failed: {}", stderr(&output));
+        echo_report(&output)
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Add the platform's executable suffix to a name.
+pub fn exe_name(name: &str) -> String {
+    name.to_string() + std::env::consts::EXE_SUFFIX
+}
+
+/// The echoargs helper as built by cargo (it is an example of this crate)
+pub fn echoargs_exe() -> PathBuf {
+    let bin_folder = Path::new(env!("CARGO_BIN_EXE_wexe")).parent().unwrap();
+    let echoargs = bin_folder.join("examples").join(exe_name("echoargs"));
+    assert!(
+        echoargs.exists(),
+        "The echoargs example has not been built: {}",
+        echoargs.display()
+    );
+    echoargs
+}
+
+/// Parse the report printed by echoargs.
+pub fn echo_report(output: &Output) -> Value {
+    let text = String::from_utf8_lossy(&output.stdout);
+    serde_json::from_str(text.trim()).unwrap_or_else(|e| panic!("Bad echoargs output ({e}): {text}"))
+}
+
+/// The arguments in an echoargs report
+pub fn echo_args(report: &Value) -> Vec<String> {
+    report["args"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|arg| arg.as_str().unwrap().to_string())
+        .collect()
+}
+
+/// The raw bytes of the arguments in an echoargs report (on Unix)
+pub fn echo_raw_args(report: &Value) -> Vec<Vec<u8>> {
+    serde_json::from_value(report["raw_args"].clone()).unwrap()
+}
+
+/// The raw bytes of an environment variable in an echoargs report (on Unix), if it is
+/// set and not valid Unicode
+pub fn echo_raw_env(report: &Value, name: &str) -> Option<Vec<u8>> {
+    serde_json::from_value(report["raw_env"][name].clone()).ok()
+}
+
+/// An environment variable in an echoargs report, if set
+pub fn echo_env<'a>(report: &'a Value, name: &str) -> Option<&'a str> {
+    report["env"][name].as_str()
+}
+
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
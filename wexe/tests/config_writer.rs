@@ -0,0 +1,132 @@
+//! Integration tests for writing application models back to TOML.
+
+use std::collections::HashMap;
+
+use toml_edit::DocumentMut;
+
+use wexe::config_model::{CONFIG_SCHEMA, ConfigArgPaths, ConfigPathMapping, ConfigRewriteRule, ListOps, PathlikeOptions, WexeApp, WexeAppConfig, app_to_toml, update_document};
+
+fn parse(text: &str) -> WexeApp {
+    let cfg: WexeAppConfig = toml::from_str(text).unwrap();
+    WexeApp::from_config(cfg)
+}
+
+fn sample_app() -> WexeApp {
+    let mut app = WexeApp::new("/opt/tool/bin/tool");
+    app.args = ListOps {
+        prepend: vec!["--config".into(), "/etc/tool.conf".into()],
+        append: vec!["--verbose".into()],
+    };
+    app.args_rewrite = vec![
+        ConfigRewriteRule {
+            pattern: Some("^/(.*)$".into()),
+            replace: Some("--$1".into()),
+            ..Default::default()
+        },
+        ConfigRewriteRule {
+            insert: Some("--color".into()),
+            ..Default::default()
+        },
+    ];
+    app.args_paths = Some(ConfigArgPaths {
+        existing: Some(false),
+        patterns: Some(vec![r"\.txt$".into()]),
+        map: Some(vec![ConfigPathMapping {
+            from: "/mnt/c/".into(),
+            to: r"C:\".into(),
+            separator: Some(r"\".into()),
+        }]),
+        ..Default::default()
+    });
+    app.env_set = HashMap::from([
+        ("TOOL_HOME".to_string(), "/opt/tool".to_string()),
+        ("TOOL_DEBUG".to_string(), String::new()),
+    ]);
+    app.env_pathlike_options = HashMap::from([(
+        "LD_LIBRARY_PATH".to_string(),
+        PathlikeOptions {
+            dedupe: true,
+            remove: vec!["/usr/local/lib".into()],
+            ..Default::default()
+        },
+    )]);
+    app.env_clear = true;
+    app.env_keep = vec!["HOME".into(), "LC_*".into()];
+    app.env_pathlike = HashMap::from([(
+        "LD_LIBRARY_PATH".to_string(),
+        ListOps {
+            prepend: vec!["/opt/tool/lib".into()],
+            append: Vec::new(),
+        },
+    )]);
+    app
+}
+
+#[test]
+fn written_configuration_reads_back_the_same() {
+    let app = sample_app();
+    let text = app_to_toml(&app);
+    let mut expected = app.clone();
+    // The template's PATH example section is kept
+    expected.env_pathlike.insert("PATH".into(), ListOps::default());
+    assert_eq!(parse(&text), expected);
+    // The comments of the template are kept
+    assert!(text.contains("# Additional arguments to prepend or append"));
+    // New configuration files declare the current schema
+    let cfg: WexeAppConfig = toml::from_str(&text).unwrap();
+    assert_eq!(cfg.schema, Some(CONFIG_SCHEMA));
+}
+
+#[test]
+fn written_configuration_is_stable() {
+    let app = sample_app();
+    let text = app_to_toml(&app);
+    let mut doc = text.parse::<DocumentMut>().unwrap();
+    update_document(&mut doc, &parse(&text));
+    assert_eq!(doc.to_string(), text);
+}
+
+#[test]
+fn update_keeps_comments_and_formatting() {
+    let text = r#"# My tool
+target = "/opt/tool/bin/tool" # the target
+
+[args]
+prepend = [
+  "--config", # which configuration
+  "/etc/tool.conf",
+]
+
+[env.set]
+# keep this comment
+TOOL_HOME = '/opt/tool'
+OLD = "remove me"
+"#;
+    let mut doc = text.parse::<DocumentMut>().unwrap();
+    let mut app = parse(text);
+    app.target = "/usr/bin/tool".into();
+    app.args.prepend[1] = "/etc/other.conf".into();
+    app.env_set.remove("OLD");
+    app.env_set.insert("NEW".into(), "added".into());
+    update_document(&mut doc, &app);
+    let updated = doc.to_string();
+    assert!(updated.contains("# My tool\ntarget = \"/usr/bin/tool\" # the target\n"));
+    assert!(updated.contains("\"--config\", # which configuration\n  \"/etc/other.conf\",\n"));
+    assert!(updated.contains("# keep this comment\nTOOL_HOME = '/opt/tool'\n"));
+    assert!(!updated.contains("OLD"));
+    assert_eq!(parse(&updated), app);
+}
+
+#[test]
+fn update_adds_missing_sections() {
+    let text = "target = \"/bin/tool\"\n";
+    let mut doc = text.parse::<DocumentMut>().unwrap();
+    let app = sample_app();
+    update_document(&mut doc, &app);
+    assert_eq!(parse(&doc.to_string()), app);
+
+    // Nothing is added for an application without arguments or environment changes
+    let mut doc = text.parse::<DocumentMut>().unwrap();
+    update_document(&mut doc, &WexeApp::new("/bin/tool"));
+    assert_eq!(doc.to_string(), text);
+}
@@ -0,0 +1,60 @@
+//! Tests for the launch log files and their rotation.
+
+mod common;
+
+use std::fs;
+
+use wexe::launch_log::{LaunchLog, LaunchRecord};
+
+use common::*;
+
+#[test]
+fn records_are_appended_as_json_lines() {
+    let sandbox = Sandbox::new("launch-log-append");
+    let log = LaunchLog {
+        path: sandbox.root().join("logs").join("app.log"),
+        max_size: 0,
+        keep: 1,
+    };
+    for code in [0, 3] {
+        let record = LaunchRecord {
+            tag: "app".into(),
+            exit_code: Some(code),
+            ..Default::default()
+        };
+        log.append(&record).unwrap();
+    }
+    let text = fs::read_to_string(&log.path).unwrap();
+    let records: Vec<serde_json::Value> =
+        text.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[1]["tag"], "app");
+    assert_eq!(records[1]["exit_code"], 3);
+    assert!(records[1]["signal"].is_null());
+}
+
+#[test]
+fn logs_are_rotated_by_size() {
+    let sandbox = Sandbox::new("launch-log-rotate");
+    let log = LaunchLog {
+        path: sandbox.root().join("app.log"),
+        max_size: 10,
+        keep: 2,
+    };
+    for index in 0..5 {
+        let record = LaunchRecord {
+            tag: format!("app{index}"),
+            ..Default::default()
+        };
+        log.append(&record).unwrap();
+    }
+    // Each record exceeds the limit, so each append rotated the previous one
+    let tag = |path| {
+        let text = fs::read_to_string(path).unwrap();
+        serde_json::from_str::<serde_json::Value>(&text).unwrap()["tag"].clone()
+    };
+    assert_eq!(tag(log.path.clone()), "app4");
+    assert_eq!(tag(log.rotated_path(1)), "app3");
+    assert_eq!(tag(log.rotated_path(2)), "app2");
+    assert!(!log.rotated_path(3).exists());
+}
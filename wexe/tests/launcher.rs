@@ -0,0 +1,617 @@
+//! Integration tests for the wexe launcher: run stubs wrapping the echoargs helper and
+//! check what the target received.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use wexe::usage_stats::read_usage_stats;
+
+use common::*;
+
+#[test]
+fn arguments_are_prepended_and_appended_in_order() {
+    let sandbox = Sandbox::installed("args-order");
+    sandbox.add_app(
+        "echo",
+        r#"
+target = "{echoargs}"
+[args]
+prepend = ["--first", "--second"]
+append = ["--last"]
+"#,
+    );
+    let report = sandbox.run_echo("echo", &["one", "two words"], &[]);
+    assert_eq!(
+        echo_args(&report),
+        vec!["--first", "--second", "one", "two words", "--last"]
+    );
+}
+
+#[test]
+fn environment_variables_are_set_and_deleted() {
+    let sandbox = Sandbox::installed("env-set");
+    sandbox.add_app(
+        "echo",
+        r#"
+target = "{echoargs}"
+[env.set]
+WEXE_TEST_SET = "new value"
+WEXE_TEST_OVERRIDE = "overridden"
+WEXE_TEST_DELETE = ""
+"#,
+    );
+    let report = sandbox.run_echo(
+        "echo",
+        &[],
+        &[
+            ("WEXE_TEST_OVERRIDE", "original"),
+            ("WEXE_TEST_DELETE", "present"),
+            ("WEXE_TEST_KEEP", "kept"),
+        ],
+    );
+    assert_eq!(echo_env(&report, "WEXE_TEST_SET"), Some("new value"));
+    assert_eq!(echo_env(&report, "WEXE_TEST_OVERRIDE"), Some("overridden"));
+    assert_eq!(echo_env(&report, "WEXE_TEST_DELETE"), None);
+    assert_eq!(echo_env(&report, "WEXE_TEST_KEEP"), Some("kept"));
+}
+
+#[test]
+fn pathlike_variables_are_joined() {
+    let sandbox = Sandbox::installed("pathlike");
+    sandbox.add_app(
+        "echo",
+        r#"
+target = "{echoargs}"
+[env.pathlike.WEXE_TEST_PATH]
+prepend = ["/opt/first", "/opt/second"]
+append = ["/opt/last"]
+[env.pathlike.WEXE_TEST_NEW_PATH]
+append = ["/opt/only"]
+"#,
+    );
+    let report = sandbox.run_echo("echo", &[], &[("WEXE_TEST_PATH", "/usr/a:/usr/b")]);
+    assert_eq!(
+        echo_env(&report, "WEXE_TEST_PATH"),
+        Some("/opt/first:/opt/second:/usr/a:/usr/b:/opt/last")
+    );
+    assert_eq!(echo_env(&report, "WEXE_TEST_NEW_PATH"), Some("/opt/only"));
+}
+
+#[test]
+fn exit_code_is_propagated() {
+    let sandbox = Sandbox::installed("exit-code");
+    sandbox.add_app(
+        "echo",
+        r#"
+target = "{echoargs}"
+[env.set]
+ECHOARGS_EXIT_CODE = "42"
+"#,
+    );
+    let output = sandbox.run_stub("echo", &[], &[]);
+    assert_eq!(output.status.code(), Some(42));
+    // the target did run
+    assert_eq!(echo_args(&echo_report(&output)), Vec::<String>::new());
+}
+
+#[test]
+fn missing_target_is_an_error() {
+    let sandbox = Sandbox::installed("missing-target");
+    sandbox.add_app("echo", r#"target = "{echoargs}""#);
+    fs::remove_file(sandbox.echoargs()).unwrap();
+    let output = sandbox.run_stub("echo", &[], &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).is_empty());
+    assert!(stderr(&output).contains("Target executable does not exist"));
+}
+
+#[test]
+fn missing_configuration_is_an_error() {
+    let sandbox = Sandbox::installed("missing-config");
+    sandbox.add_app("echo", r#"target = "{echoargs}""#);
+    fs::remove_file(sandbox.config("echo")).unwrap();
+    let output = sandbox.run_stub("echo", &[], &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("No configuration file for 'echo'"));
+}
+
+#[test]
+fn wexe_runs_an_application_given_as_first_argument() {
+    let sandbox = Sandbox::installed("redirect");
+    sandbox.add_app(
+        "echo",
+        r#"
+target = "{echoargs}"
+[args]
+prepend = ["--first"]
+"#,
+    );
+    let mut command = Command::new(sandbox.stub("wexe"));
+    command.args(["echo", "one"]);
+    sandbox.sandboxed(&mut command);
+    let output = command.output().unwrap();
+    assert!(output.status.success(), "wexe failed: {}", stderr(&output));
+    assert_eq!(echo_args(&echo_report(&output)), vec!["--first", "one"]);
+}
+
+#[test]
+fn configuration_errors_are_reported_with_their_location() {
+    let sandbox = Sandbox::installed("config-errors");
+    sandbox.add_app("echo", r#"target = "{echoargs}""#);
+    sandbox.write_config("echo", "target = \"{echoargs}\"\n[args]\nprepend = 3\n");
+    let output = sandbox.run_stub("echo", &[], &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("echo.toml, line 3, column 11"), "{}", stderr(&output));
+
+    sandbox.write_config("echo", r#"target = "relative/echoargs""#);
+    let output = sandbox.run_stub("echo", &[], &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("Target executable path must be absolute: relative/echoargs"));
+}
+
+#[test]
+fn configurations_for_a_newer_schema_are_rejected() {
+    let sandbox = Sandbox::installed("newer-schema");
+    sandbox.add_app("echo", "target = \"{echoargs}\"\nschema = 1\n");
+    sandbox.run_echo("echo", &[], &[]);
+
+    sandbox.write_config("echo", "target = \"{echoargs}\"\nschema = 99\n");
+    let output = sandbox.run_stub("echo", &[], &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("uses configuration schema 99"), "{}", stderr(&output));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_linux_section_is_applied() {
+    let sandbox = Sandbox::installed("os-linux");
+    sandbox.add_app(
+        "echo",
+        r#"
+target = "/no/such/base/target"
+[args]
+prepend = ["--base"]
+[env.set]
+WEXE_TEST_OS = "base"
+[os.linux]
+target = "{echoargs}"
+[os.linux.args]
+prepend = ["--linux"]
+[os.linux.env.set]
+WEXE_TEST_OS = "linux"
+[os.windows.args]
+prepend = ["--windows"]
+"#,
+    );
+    let report = sandbox.run_echo("echo", &["arg"], &[]);
+    assert_eq!(echo_args(&report), vec!["--base", "--linux", "arg"]);
+    assert_eq!(echo_env(&report, "WEXE_TEST_OS"), Some("linux"));
+}
+
+#[test]
+fn profiles_are_selected_by_argument_environment_or_default() {
+    let sandbox = Sandbox::installed("profiles");
+    sandbox.add_app(
+        "echo",
+        r#"
+target = "{echoargs}"
+default_profile = "release"
+[args]
+prepend = ["--base"]
+[profiles.debug.args]
+prepend = ["--debug"]
+[profiles.debug.env.set]
+WEXE_TEST_MODE = "debug"
+[profiles.release.args]
+append = ["--release"]
+"#,
+    );
+    let report = sandbox.run_echo("echo", &["arg"], &[]);
+    assert_eq!(echo_args(&report), vec!["--base", "arg", "--release"]);
+    assert_eq!(echo_env(&report, "WEXE_TEST_MODE"), None);
+
+    let report = sandbox.run_echo("echo", &["arg"], &[("WEXE_PROFILE", "debug")]);
+    assert_eq!(echo_args(&report), vec!["--base", "--debug", "arg"]);
+    assert_eq!(echo_env(&report, "WEXE_TEST_MODE"), Some("debug"));
+
+    // The argument wins over the environment; a profile the application does not have
+    // in the environment is ignored
+    let report = sandbox.run_echo("echo", &["+release", "arg"], &[("WEXE_PROFILE", "debug")]);
+    assert_eq!(echo_args(&report), vec!["--base", "arg", "--release"]);
+    let report = sandbox.run_echo("echo", &["arg"], &[("WEXE_PROFILE", "other")]);
+    assert_eq!(echo_args(&report), vec!["--base", "arg", "--release"]);
+
+    // A first argument naming no profile is passed on; an explicitly requested profile
+    // must exist
+    let report = sandbox.run_echo("echo", &["+other", "arg"], &[]);
+    assert_eq!(echo_args(&report), vec!["--base", "+other", "arg", "--release"]);
+    let output = sandbox.run_stub("echo", &["--wexe-profile=other"], &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("Unknown profile 'other'"), "{}", stderr(&output));
+}
+
+#[test]
+fn plus_arguments_are_passed_on_without_profiles() {
+    let sandbox = Sandbox::installed("no-profiles");
+    sandbox.add_app("echo", r#"target = "{echoargs}""#);
+    let report = sandbox.run_echo("echo", &["+10", "file"], &[]);
+    assert_eq!(echo_args(&report), vec!["+10", "file"]);
+}
+
+#[test]
+fn launch_flags_control_wexe() {
+    let sandbox = Sandbox::installed("launch-flags");
+    sandbox.add_app(
+        "echo",
+        r#"
+target = "{echoargs}"
+[args]
+prepend = ["--first"]
+append = ["--last"]
+"#,
+    );
+    let report = sandbox.run_echo("echo", &["--wexe-no-args", "arg", "--wexe--", "--wexe-show"], &[]);
+    assert_eq!(echo_args(&report), vec!["arg", "--wexe-show"]);
+
+    // Neither showing nor a dry run runs the target
+    let output = sandbox.run_stub("echo", &["arg", "--wexe-show"], &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("echo.toml"));
+    assert!(!stdout(&output).contains("\"cwd\""));
+    let output = sandbox.run_stub("echo", &["--wexe-dry-run", "arg"], &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains(r#""--first", "arg", "--last"]"#), "{}", stdout(&output));
+    assert!(!stdout(&output).contains("\"cwd\""));
+
+    let output = sandbox.run_stub("echo", &["--wexe-bogus"], &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("Unknown wexe flag: --wexe-bogus"));
+
+    // With another prefix, --wexe- arguments are passed to the target
+    sandbox.write_config("echo", "target = \"{echoargs}\"\nwexe_flag_prefix = \"+wexe:\"\n");
+    let report = sandbox.run_echo("echo", &["--wexe-show", "+wexe:debug"], &[]);
+    assert_eq!(echo_args(&report), vec!["--wexe-show"]);
+}
+
+#[test]
+fn arguments_are_rewritten_and_traced() {
+    let sandbox = Sandbox::installed("args-rewrite");
+    sandbox.add_app(
+        "echo",
+        r#"
+target = "{echoargs}"
+[args]
+prepend = ["/kept"]
+[[args.rewrite]]
+match = '^/(\w+)$'
+replace = '--$1'
+[[args.rewrite]]
+match = '^--drop-me$'
+drop = true
+"#,
+    );
+    let output = sandbox.run_stub("echo", &["/help", "--drop-me", "x"], &[("WEXE_DEBUG", "1")]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    // Only the given arguments are rewritten
+    assert_eq!(echo_args(&echo_report(&output)), vec!["/kept", "--help", "x"]);
+    assert!(stderr(&output).contains(r#"rule 1: replaced "/help" by "--help""#));
+    assert!(stderr(&output).contains(r#"rule 2: dropped "--drop-me""#));
+
+    sandbox.write_config(
+        "echo",
+        "target = \"{echoargs}\"\n[[args.rewrite]]\nmatch = '('\nreplace = ''\n",
+    );
+    let output = sandbox.run_stub("echo", &[], &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("argument rewrite rule 1 is invalid"), "{}", stderr(&output));
+}
+
+#[test]
+fn path_arguments_are_translated() {
+    let sandbox = Sandbox::installed("args-paths");
+    let data = sandbox.root().join("data");
+    fs::create_dir_all(&data).unwrap();
+    fs::write(data.join("input.txt"), "").unwrap();
+    let root = sandbox.root().to_string_lossy().to_string();
+    sandbox.add_app(
+        "echo",
+        &format!(
+            "target = \"{{echoargs}}\"\n[args.paths]\nexisting = true\n\
+            [[args.paths.map]]\nfrom = \"{root}/\"\n\
+            to = 'X:\\'\nseparator = '\\'\n"
+        ),
+    );
+    let input = data.join("input.txt").to_string_lossy().to_string();
+    let output = sandbox.run_stub("echo", &[&input, "--flag"], &[("WEXE_DEBUG", "1")]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(echo_args(&echo_report(&output)), vec![r"X:\data\input.txt", "--flag"]);
+    assert!(stderr(&output).contains("translated to"), "{}", stderr(&output));
+
+    sandbox.write_config("echo", "target = \"{echoargs}\"\n[args.paths]\nmatch = ['(']\n");
+    let output = sandbox.run_stub("echo", &[], &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("invalid path argument pattern"), "{}", stderr(&output));
+}
+
+#[test]
+fn cleared_environment_keeps_only_matching_variables() {
+    let sandbox = Sandbox::installed("env-clear");
+    sandbox.add_app(
+        "echo",
+        r#"
+target = "{echoargs}"
+[env]
+clear = true
+keep = ["WEXE_TEST_KEEP*"]
+[env.set]
+WEXE_TEST_SET = "set"
+[env.pathlike.WEXE_TEST_PATH]
+append = ["/opt/last"]
+"#,
+    );
+    let report = sandbox.run_echo(
+        "echo",
+        &[],
+        &[
+            ("WEXE_TEST_KEEP_ME", "kept"),
+            ("WEXE_TEST_DROPPED", "dropped"),
+            ("WEXE_TEST_PATH", "/usr/a"),
+        ],
+    );
+    assert_eq!(echo_env(&report, "WEXE_TEST_KEEP_ME"), Some("kept"));
+    assert_eq!(echo_env(&report, "WEXE_TEST_DROPPED"), None);
+    assert_eq!(echo_env(&report, "HOME"), None);
+    assert_eq!(echo_env(&report, "WEXE_TEST_SET"), Some("set"));
+    // PATH-like variables start from the kept environment
+    assert_eq!(echo_env(&report, "WEXE_TEST_PATH"), Some("/opt/last"));
+}
+
+#[test]
+fn arguments_and_variables_need_not_be_unicode() {
+    use std::ffi::{OsStr, OsString};
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+    let sandbox = Sandbox::installed("non-unicode");
+    sandbox.add_app(
+        "echo",
+        r#"
+target = "{echoargs}"
+[[args.rewrite]]
+match = '^/(\w+)$'
+replace = '--$1'
+[env.pathlike.WEXE_TEST_PATH]
+prepend = ["/opt/first"]
+"#,
+    );
+    let invalid = OsString::from_vec(b"file-\xff\xfe.txt".to_vec());
+    let path = OsString::from_vec(b"/usr/\xc3(".to_vec());
+    let output = sandbox.run_stub_os(
+        "echo",
+        &[OsStr::new("/help"), &invalid, OsStr::new("--wexe-debug")],
+        &[("WEXE_TEST_PATH", &path)],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let report = echo_report(&output);
+    // The invalid argument is passed on as is; the others are still rewritten or parsed
+    assert_eq!(
+        echo_raw_args(&report),
+        vec![b"--help".to_vec(), invalid.as_bytes().to_vec()]
+    );
+    assert_eq!(
+        echo_raw_env(&report, "WEXE_TEST_PATH"),
+        Some(b"/opt/first:/usr/\xc3(".to_vec())
+    );
+
+    // A PATH-like element with the separator is reported, not a crash
+    sandbox.write_config(
+        "echo",
+        "target = \"{echoargs}\"\n[env.pathlike.WEXE_TEST_PATH]\nappend = [\"/a:b\"]\n",
+    );
+    let output = sandbox.run_stub_os("echo", &[&invalid], &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains(r#"element "/a:b" is invalid"#), "{}", stderr(&output));
+}
+
+#[cfg(unix)]
+#[test]
+fn wexecfg_in_a_non_unicode_folder_is_an_error() {
+    use std::ffi::OsString;
+    use std::os::unix::ffi::OsStringExt;
+
+    let sandbox = Sandbox::installed("non-unicode-install");
+    let folder = sandbox.root().join(OsString::from_vec(b"bin-\xff".to_vec()));
+    fs::create_dir_all(&folder).unwrap();
+    for name in ["wexe", "wexecfg"] {
+        fs::copy(sandbox.stub(name), folder.join(exe_name(name))).unwrap();
+    }
+    let mut command = Command::new(folder.join(exe_name("wexe")));
+    command.arg("/list");
+    sandbox.sandboxed(&mut command);
+    let output = command.output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("Path is not valid Unicode"), "{}", stderr(&output));
+}
+
+#[test]
+fn launches_are_logged() {
+    let sandbox = Sandbox::installed("launch-log");
+    sandbox.add_app(
+        "echo",
+        r#"
+target = "{echoargs}"
+log = "logs/echo.log"
+[env.set]
+WEXE_TEST_SET = "value"
+"#,
+    );
+    let output = sandbox.run_stub("echo", &["one"], &[("ECHOARGS_EXIT_CODE", "5")]);
+    assert_eq!(output.status.code(), Some(5));
+    let text = fs::read_to_string(sandbox.wexe_home().join("logs").join("echo.log")).unwrap();
+    let record: serde_json::Value = serde_json::from_str(text.trim()).unwrap();
+    assert_eq!(record["tag"], "echo");
+    assert_eq!(record["argv"][1], "one");
+    assert_eq!(record["env"]["WEXE_TEST_SET"], "value");
+    assert_eq!(record["exit_code"], 5);
+    assert!(record["pid"].as_u64().is_some());
+    assert_eq!(record["cwd"], sandbox.root().to_string_lossy().as_ref());
+
+    // Without its own log, an application uses the log of the settings
+    sandbox.write_config("echo", "target = \"{echoargs}\"\n");
+    let output = sandbox.wexecfg(&["/settings", "log", "all.log"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    sandbox.run_echo("echo", &[], &[]);
+    assert!(sandbox.wexe_home().join("all.log").exists());
+}
+
+#[test]
+fn launches_are_counted_when_stats_are_enabled() {
+    let sandbox = Sandbox::installed("usage-stats");
+    sandbox.add_app("echo", "target = \"{echoargs}\"\n");
+    sandbox.add_app("idle", "target = \"{echoargs}\"\n");
+    // Statistics are off by default
+    sandbox.run_echo("echo", &[], &[]);
+    assert!(!sandbox.wexe_home().join("wexe.stats.json").exists());
+
+    let output = sandbox.wexecfg(&["/settings", "stats", "true"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    sandbox.run_echo("echo", &[], &[]);
+    let output = sandbox.run_stub("echo", &[], &[("ECHOARGS_EXIT_CODE", "2")]);
+    assert_eq!(output.status.code(), Some(2));
+    let stats = read_usage_stats(&sandbox.wexe_home().join("wexe.stats.json")).unwrap();
+    assert_eq!(stats["echo"].launches, 2);
+    assert_eq!(stats["echo"].nonzero_exits, 1);
+    assert!(stats["echo"].last_used_time().is_some());
+    assert!(!stats.contains_key("idle"));
+
+    let output = sandbox.wexecfg(&["/stats"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let text = stdout(&output);
+    assert!(text.contains("never"));
+    assert!(text.contains("not used in the last"));
+    assert!(text.contains("idle"));
+    let output = sandbox.wexecfg(&["/list", "--stats"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Last used"));
+}
+
+#[test]
+fn timeout_stops_a_hanging_target() {
+    let sandbox = Sandbox::installed("timeout");
+    sandbox.add_app(
+        "echo",
+        r#"
+target = "{echoargs}"
+timeout = "200ms"
+log = "echo.log"
+"#,
+    );
+    let start = Instant::now();
+    let output = sandbox.run_stub("echo", &[], &[("ECHOARGS_SLEEP_MS", "20000")]);
+    assert_eq!(output.status.code(), Some(124), "{}", stderr(&output));
+    assert!(start.elapsed() < Duration::from_secs(10));
+    let text = fs::read_to_string(sandbox.wexe_home().join("echo.log")).unwrap();
+    let record: serde_json::Value = serde_json::from_str(text.trim()).unwrap();
+    assert_eq!(record["timed_out"], true);
+
+    // The environment overrides the configuration; 0 disables the timeout
+    let output = sandbox.run_stub(
+        "echo",
+        &[],
+        &[("ECHOARGS_SLEEP_MS", "400"), ("WEXE_TIMEOUT", "0")],
+    );
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    let output = sandbox.run_stub("echo", &[], &[("WEXE_TIMEOUT", "soon")]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("WEXE_TIMEOUT"));
+}
+
+#[cfg(unix)]
+#[test]
+fn timeout_kills_a_target_ignoring_sigterm() {
+    let sandbox = Sandbox::installed("timeout-kill");
+    sandbox.add_app(
+        "stubborn",
+        r#"
+target = "/bin/sh"
+timeout = "100ms"
+kill_after = "200ms"
+[args]
+prepend = ["-c", "trap '' TERM; exec sleep 20"]
+"#,
+    );
+    let start = Instant::now();
+    let output = sandbox.run_stub("stubborn", &[], &[("WEXE_DEBUG", "1")]);
+    assert_eq!(output.status.code(), Some(124));
+    assert!(start.elapsed() < Duration::from_secs(10));
+    let text = stderr(&output);
+    assert!(text.contains("sent SIGTERM"), "{text}");
+    assert!(text.contains("killing process"), "{text}");
+}
+
+#[cfg(unix)]
+#[test]
+fn failing_targets_are_retried() {
+    let sandbox = Sandbox::installed("retry");
+    // Fails with code 75 until its third run; reports the run and its first input line
+    sandbox.add_app(
+        "flaky",
+        r#"
+target = "/bin/sh"
+[args]
+prepend = ["-c", "read line; n=$(cat count 2>/dev/null || echo 0); n=$((n+1)); echo $n > count; echo $n:$line; [ $n -ge 3 ] || exit 75"]
+[retry]
+attempts = 3
+backoff = "exponential"
+delay = "10ms"
+on_exit_codes = [75]
+"#,
+    );
+    let count = sandbox.root().join("count");
+    let output = sandbox.run_stub("flaky", &[], &[("WEXE_DEBUG", "1")]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "1:\n2:\n3:\n");
+    assert!(stderr(&output).contains("retrying in"));
+
+    // A file on standard input is rewound for each attempt
+    fs::remove_file(&count).unwrap();
+    let input = sandbox.root().join("input.txt");
+    fs::write(&input, "first\nsecond\nthird\n").unwrap();
+    let mut command = Command::new(sandbox.stub("flaky"));
+    sandbox.sandboxed(&mut command);
+    command.stdin(fs::File::open(&input).unwrap());
+    let output = command.output().unwrap();
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "1:first\n2:first\n3:first\n");
+
+    // A pipe cannot be replayed, so there are no retries
+    fs::remove_file(&count).unwrap();
+    let mut command = Command::new(sandbox.stub("flaky"));
+    sandbox.sandboxed(&mut command);
+    command
+        .env("WEXE_DEBUG", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = command.spawn().unwrap();
+    child.stdin.take().unwrap().write_all(b"piped\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(75));
+    assert_eq!(stdout(&output), "1:piped\n");
+    assert!(stderr(&output).contains("Retries disabled"));
+
+    // Exit codes that are not listed are not retried
+    sandbox.write_config(
+        "flaky",
+        "target = \"/bin/sh\"\n[args]\nprepend = [\"-c\", \"echo run; exit 3\"]\n\
+        [retry]\nattempts = 3\ndelay = \"10ms\"\non_exit_codes = [75]\n",
+    );
+    let output = sandbox.run_stub("flaky", &[], &[]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(stdout(&output), "run\n");
+}
@@ -0,0 +1,360 @@
+//! Integration tests for the launcher library API.
+
+mod common;
+
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use wexe::config_model::{
+    ConfigError, ConfigRetry, ListOps, WexeApp, get_config_file, read_config_file,
+};
+use wexe::launch_retry::{Backoff, RetryPolicy};
+use wexe::launch_timeout::{TimeoutOutcome, format_duration, parse_duration};
+use wexe::launcher::{LaunchError, LaunchFlags, Launcher, normalize_path};
+
+use common::*;
+
+fn echo_app(pathlike: HashMap<String, ListOps>) -> WexeApp {
+    WexeApp {
+        target: echoargs_exe().to_string_lossy().to_string(),
+        args: ListOps {
+            prepend: vec!["--first".into()],
+            append: vec!["--last".into()],
+        },
+        args_rewrite: Vec::new(),
+        args_paths: None,
+        env_set: HashMap::from([
+            ("WEXE_API_SET".to_string(), "value".to_string()),
+            ("WEXE_API_DELETE".to_string(), String::new()),
+        ]),
+        env_pathlike: pathlike,
+        env_pathlike_options: HashMap::new(),
+        env_clear: false,
+        env_keep: Vec::new(),
+        timeout: None,
+        kill_after: None,
+        retry: None,
+    }
+}
+
+#[test]
+fn launcher_computes_arguments_and_environment() {
+    let launcher = Launcher::new(echo_app(HashMap::new()));
+    assert_eq!(
+        launcher.effective_args(vec!["middle".into()]),
+        vec!["--first", "middle", "--last"]
+    );
+    let changes = launcher.env_changes().unwrap();
+    assert_eq!(changes["WEXE_API_SET"], Some("value".into()));
+    assert_eq!(changes["WEXE_API_DELETE"], None);
+    let env = launcher.effective_env().unwrap();
+    assert_eq!(env.get(OsStr::new("WEXE_API_SET")), Some(&"value".into()));
+    assert!(!env.contains_key(OsStr::new("WEXE_API_DELETE")));
+}
+
+#[test]
+fn launcher_runs_the_target() {
+    let launcher = Launcher::new(echo_app(HashMap::new()));
+    let output = launcher.command(vec!["middle".into()]).unwrap().output().unwrap();
+    assert!(output.status.success());
+    let report = echo_report(&output);
+    assert_eq!(echo_args(&report), vec!["--first", "middle", "--last"]);
+    assert_eq!(echo_env(&report, "WEXE_API_SET"), Some("value"));
+}
+
+#[test]
+fn launcher_reports_a_missing_target() {
+    let mut app = echo_app(HashMap::new());
+    app.target = "/no/such/target".into();
+    let launcher = Launcher::new(app);
+    assert!(matches!(
+        launcher.command(Vec::new()),
+        Err(LaunchError::TargetNotFound(_))
+    ));
+}
+
+#[test]
+fn launcher_applies_the_timeout() {
+    let mut app = echo_app(HashMap::new());
+    app.env_set.insert("ECHOARGS_SLEEP_MS".into(), "5000".into());
+    app.timeout = Some(Duration::from_millis(100));
+    app.kill_after = Some(Duration::ZERO);
+    let launcher = Launcher::new(app);
+
+    let mut cmd = launcher.command(Vec::new()).unwrap();
+    cmd.stdout(Stdio::null());
+    let outcome = launcher.run_command(&mut cmd).unwrap();
+    assert!(outcome.pid.is_some());
+    let (status, timeout) = outcome.result.unwrap();
+    assert!(!status.success());
+    assert_eq!(timeout, TimeoutOutcome::Killed);
+
+    let mut child = launcher.spawn(Vec::new()).unwrap();
+    let (_, timeout) = launcher.wait(&mut child).unwrap();
+    assert_eq!(timeout, TimeoutOutcome::Killed);
+}
+
+#[test]
+fn launcher_retries_a_failing_target() {
+    let mut app = echo_app(HashMap::new());
+    app.env_set.insert("ECHOARGS_EXIT_CODE".into(), "75".into());
+    let retry = ConfigRetry {
+        attempts: Some(3),
+        delay: Some("10ms".into()),
+        // Whatever the standard input of the test is, retry
+        piped_stdin: Some(true),
+        ..Default::default()
+    };
+    app.retry = Some(RetryPolicy::from_config(&retry).unwrap());
+    let launcher = Launcher::new(app);
+
+    let mut cmd = launcher.command(Vec::new()).unwrap();
+    cmd.stdout(Stdio::null());
+    let outcome = launcher.run_command(&mut cmd).unwrap();
+    assert_eq!(outcome.attempts, 3);
+    let (status, timeout) = outcome.result.unwrap();
+    assert_eq!(status.code(), Some(75));
+    assert_eq!(timeout, TimeoutOutcome::Exited);
+}
+
+#[cfg(unix)]
+#[test]
+fn launcher_reports_an_invalid_pathlike_element() {
+    let pathlike = HashMap::from([(
+        "WEXE_API_PATH".to_string(),
+        ListOps {
+            prepend: vec!["/has:separator".into()],
+            append: Vec::new(),
+        },
+    )]);
+    let launcher = Launcher::new(echo_app(pathlike));
+    match launcher.env_changes() {
+        Err(LaunchError::InvalidPathlike {
+            variable, entry, ..
+        }) => {
+            assert_eq!(variable, "WEXE_API_PATH");
+            assert_eq!(entry, PathBuf::from("/has:separator"));
+        }
+        other => panic!("Unexpected result: {other:?}"),
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn paths_are_normalized_lexically() {
+    let cases = [
+        ("/a/b/", "/a/b"),
+        ("/a/./b/../c", "/a/c"),
+        ("/..", "/"),
+        ("a/../..", ".."),
+        ("./", "."),
+    ];
+    for (path, expected) in cases {
+        assert_eq!(normalize_path(Path::new(path)), PathBuf::from(expected), "{path}");
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn pathlike_options_clean_up_the_value() {
+    let sandbox = Sandbox::new("pathlike-options");
+    let existing = sandbox.root().to_string_lossy().to_string();
+    let config = format!(
+        r#"
+target = "/bin/app"
+[env.pathlike.WEXE_API_CLEAN]
+prepend = ["{existing}/", "/wexe/missing"]
+append = ["{existing}/./"]
+dedupe = true
+normalize = true
+remove = ["/wexe/home/"]
+[env.pathlike.WEXE_API_MISSING]
+prepend = ["{existing}", "/wexe/missing"]
+drop_missing = true
+"#
+    );
+    let cfg_file = sandbox.root().join("app.toml");
+    fs::write(&cfg_file, config).unwrap();
+    let launcher = Launcher::new(read_config_file(cfg_file).unwrap());
+    // SAFETY: no other test reads or writes these variables
+    unsafe {
+        std::env::set_var("WEXE_API_CLEAN", "/wexe/home:/usr/bin:/wexe/missing/");
+        std::env::set_var("WEXE_API_MISSING", "/wexe/other");
+    }
+    let changes = launcher.env_changes().unwrap();
+    assert_eq!(
+        changes["WEXE_API_CLEAN"],
+        Some(format!("{existing}:/wexe/missing:/usr/bin").into())
+    );
+    assert_eq!(changes["WEXE_API_MISSING"], Some(existing.into()));
+}
+
+#[test]
+fn configuration_errors_are_typed() {
+    let sandbox = Sandbox::new("config-error-types");
+    let cfg_file = sandbox.root().join("app.toml");
+
+    let result = read_config_file(cfg_file.clone());
+    assert!(matches!(result, Err(ConfigError::Unreadable { .. })));
+
+    fs::write(&cfg_file, "target = '/bin/app'\nargs = 3\n").unwrap();
+    match read_config_file(cfg_file.clone()) {
+        Err(ConfigError::Parse { line_col, span, .. }) => {
+            assert_eq!(line_col, Some((2, 8)));
+            assert_eq!(span, Some(27..28));
+        }
+        other => panic!("Unexpected result: {other:?}"),
+    }
+
+    fs::write(&cfg_file, "target = 'bin/app'\n").unwrap();
+    match read_config_file(cfg_file.clone()) {
+        Err(ConfigError::NonAbsoluteTarget { target, .. }) => assert_eq!(target, "bin/app"),
+        other => panic!("Unexpected result: {other:?}"),
+    }
+
+    fs::write(&cfg_file, "target = '/bin/app'\ntimeout = '5 minutes'\n").unwrap();
+    match read_config_file(cfg_file.clone()) {
+        Err(ConfigError::InvalidDuration { key, value, .. }) => {
+            assert_eq!(key, "timeout");
+            assert_eq!(value, "5 minutes");
+        }
+        other => panic!("Unexpected result: {other:?}"),
+    }
+
+    fs::write(&cfg_file, "target = '/bin/app'\n[retry]\nbackoff = 'linear'\n").unwrap();
+    match read_config_file(cfg_file.clone()) {
+        Err(ConfigError::InvalidRetry { message, .. }) => assert!(message.contains("linear")),
+        other => panic!("Unexpected result: {other:?}"),
+    }
+
+    // Keys that belong to another kind of section are reported, not ignored
+    fs::write(&cfg_file, "target = '/bin/app'\n[args]\ndedupe = true\n").unwrap();
+    match read_config_file(cfg_file.clone()) {
+        Err(ConfigError::Parse { message, .. }) => assert!(message.contains("dedupe")),
+        other => panic!("Unexpected result: {other:?}"),
+    }
+    let misplaced = "target = '/bin/app'\n[env.pathlike.PATH]\nrewrite = []\n";
+    fs::write(&cfg_file, misplaced).unwrap();
+    match read_config_file(cfg_file.clone()) {
+        Err(ConfigError::Parse { message, .. }) => assert!(message.contains("rewrite")),
+        other => panic!("Unexpected result: {other:?}"),
+    }
+
+    assert!(matches!(
+        get_config_file("Not-A-Tag".to_string()),
+        Err(ConfigError::InvalidTag(_))
+    ));
+}
+
+#[test]
+fn durations_are_parsed() {
+    let cases = [
+        ("10", Some(Duration::from_secs(10))),
+        ("500ms", Some(Duration::from_millis(500))),
+        ("1.5h", Some(Duration::from_secs(5400))),
+        ("30m", Some(Duration::from_secs(1800))),
+        ("2d", Some(Duration::from_secs(172800))),
+        ("m", None),
+        ("-1s", None),
+        ("10 parsecs", None),
+    ];
+    for (text, expected) in cases {
+        assert_eq!(parse_duration(text), expected, "{text}");
+    }
+    assert_eq!(format_duration(Duration::from_secs(1800)), "30m");
+    assert_eq!(format_duration(Duration::from_millis(1500)), "1500ms");
+}
+
+#[test]
+fn retry_delays_follow_the_backoff() {
+    let mut retry = ConfigRetry {
+        attempts: Some(4),
+        delay: Some("100ms".into()),
+        ..Default::default()
+    };
+    let policy = RetryPolicy::from_config(&retry).unwrap();
+    assert_eq!(policy.backoff, Backoff::Fixed);
+    assert_eq!(policy.delay_before(3), Duration::from_millis(100));
+    retry.backoff = Some("exponential".into());
+    let policy = RetryPolicy::from_config(&retry).unwrap();
+    let delays: Vec<Duration> = (1..4).map(|retry| policy.delay_before(retry)).collect();
+    assert_eq!(delays, [100, 200, 400].map(Duration::from_millis));
+    retry.attempts = Some(0);
+    assert!(RetryPolicy::from_config(&retry).is_err());
+}
+
+fn strings(args: &[&str]) -> Vec<OsString> {
+    args.iter().map(OsString::from).collect()
+}
+
+fn strings_of(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn launch_flags_are_taken_out_of_the_arguments() {
+    let profiles = strings_of(&["debug", "one"]);
+    let (flags, args) = LaunchFlags::parse(
+        "--wexe-",
+        &profiles,
+        strings(&["+debug", "a", "--wexe-show", "b", "--wexe-no-args", "--wexe-dry-run"]),
+    )
+    .unwrap();
+    assert_eq!(
+        flags,
+        LaunchFlags {
+            show: true,
+            dry_run: true,
+            debug: false,
+            profile: Some("debug".into()),
+            no_args: true,
+        }
+    );
+    assert_eq!(args, vec!["a", "b"]);
+
+    // The terminator passes everything after it as is; a later profile flag wins
+    let (flags, args) = LaunchFlags::parse(
+        "--wexe-",
+        &profiles,
+        strings(&["+one", "--wexe-profile=two", "--wexe-debug", "--wexe--", "--wexe-show", "+x"]),
+    )
+    .unwrap();
+    assert!(flags.debug && !flags.show);
+    assert_eq!(flags.profile.as_deref(), Some("two"));
+    assert_eq!(args, vec!["--wexe-show", "+x"]);
+
+    assert!(matches!(
+        LaunchFlags::parse("--wexe-", &profiles, strings(&["--wexe-bogus"])),
+        Err(LaunchError::UnknownFlag(flag)) if flag == "--wexe-bogus"
+    ));
+    assert!(LaunchFlags::parse("--wexe-", &profiles, strings(&["--wexe-profile="])).is_err());
+
+    // A first argument naming no profile is not reserved
+    for first in ["+10", "+other", "+"] {
+        let (flags, args) = LaunchFlags::parse("--wexe-", &profiles, strings(&[first, "a"])).unwrap();
+        assert_eq!(flags.profile, None);
+        assert_eq!(args, vec![first, "a"]);
+    }
+}
+
+#[test]
+fn launch_flag_prefix_is_configurable() {
+    let (flags, args) = LaunchFlags::parse(
+        "++",
+        &[],
+        strings(&["--wexe-show", "++dry-run", "+", "++-", "++show"]),
+    )
+    .unwrap();
+    assert!(flags.dry_run && !flags.show);
+    assert_eq!(args, vec!["--wexe-show", "+", "++show"]);
+
+    // An empty prefix disables the flags (but not the profile argument)
+    let (flags, args) = LaunchFlags::parse("", &strings_of(&["p"]), strings(&["+p", "--wexe-show"])).unwrap();
+    assert_eq!(flags.profile.as_deref(), Some("p"));
+    assert!(!flags.show);
+    assert_eq!(args, vec!["--wexe-show"]);
+}
@@ -0,0 +1,99 @@
+//! Tests for the per-operating-system `[os.*]` sections of application configurations.
+
+use std::collections::HashMap;
+
+use wexe::config_model::{ListOps, WexeApp, WexeAppConfig};
+
+const CONFIG: &str = r#"
+target = "/opt/base/tool"
+
+[args]
+prepend = ["--base-first"]
+append = ["--base-last"]
+
+[env.set]
+SHARED = "base"
+BASE_ONLY = "base"
+
+[env.pathlike.PATH]
+prepend = ["/opt/base/bin"]
+
+[os.linux]
+target = "/opt/linux/tool"
+
+[os.linux.args]
+prepend = ["--linux-first"]
+append = ["--linux-last"]
+
+[os.linux.env.set]
+SHARED = "linux"
+BASE_ONLY = ""
+
+[os.linux.env.pathlike.PATH]
+prepend = ["/opt/linux/bin"]
+append = ["/opt/linux/late"]
+
+[os.linux.env.pathlike.LD_LIBRARY_PATH]
+append = ["/opt/linux/lib"]
+
+[os.windows]
+target = 'C:\tool\tool.exe'
+"#;
+
+fn config() -> WexeAppConfig {
+    toml::from_str(CONFIG).unwrap()
+}
+
+#[test]
+fn the_section_for_the_given_os_is_selected() {
+    assert_eq!(
+        config().os_sections("linux"),
+        vec![("linux".to_string(), true), ("windows".to_string(), false)]
+    );
+    assert_eq!(WexeApp::from_config_for_os(config(), "linux").target, "/opt/linux/tool");
+    assert_eq!(WexeApp::from_config_for_os(config(), "windows").target, "C:\\tool\\tool.exe");
+}
+
+#[test]
+fn without_a_matching_section_the_base_configuration_applies() {
+    let app = WexeApp::from_config_for_os(config(), "macos");
+    assert_eq!(app, WexeApp::from_config(config()));
+    assert_eq!(app.target, "/opt/base/tool");
+    assert_eq!(app.args.prepend, vec!["--base-first"]);
+}
+
+#[test]
+fn sections_extend_arguments_and_environment() {
+    let app = WexeApp::from_config_for_os(config(), "linux");
+    assert_eq!(
+        app.args,
+        ListOps {
+            prepend: vec!["--base-first".into(), "--linux-first".into()],
+            append: vec!["--base-last".into(), "--linux-last".into()],
+        }
+    );
+    assert_eq!(
+        app.env_set,
+        HashMap::from([
+            ("SHARED".to_string(), "linux".to_string()),
+            ("BASE_ONLY".to_string(), String::new()),
+        ])
+    );
+    assert_eq!(
+        app.env_pathlike["PATH"],
+        ListOps {
+            prepend: vec!["/opt/base/bin".into(), "/opt/linux/bin".into()],
+            append: vec!["/opt/linux/late".into()],
+        }
+    );
+    assert_eq!(app.env_pathlike["LD_LIBRARY_PATH"].append, vec!["/opt/linux/lib"]);
+}
+
+#[test]
+fn a_section_without_target_keeps_the_base_target() {
+    let cfg: WexeAppConfig =
+        toml::from_str("target = '/opt/base/tool'\n[os.linux.args]\nappend = ['--x']\n").unwrap();
+    let app = WexeApp::from_config_for_os(cfg, "linux");
+    assert_eq!(app.target, "/opt/base/tool");
+    assert_eq!(app.args.append, vec!["--x"]);
+}
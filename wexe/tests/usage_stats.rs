@@ -0,0 +1,64 @@
+//! Tests for the usage statistics file and its locking.
+
+mod common;
+
+use std::fs;
+use std::io;
+use std::thread;
+
+use chrono::{Duration, Utc};
+
+use wexe::usage_stats::{read_usage_stats, record_launch};
+
+use common::*;
+
+#[test]
+fn concurrent_launches_are_all_counted() {
+    let sandbox = Sandbox::new("usage-stats-concurrent");
+    let stats_file = sandbox.root().join("wexe.stats.json");
+    assert!(read_usage_stats(&stats_file).unwrap().is_empty());
+    let threads: Vec<_> = (0..8)
+        .map(|index| {
+            let stats_file = stats_file.clone();
+            thread::spawn(move || {
+                for _ in 0..10 {
+                    let tag = if index % 2 == 0 { "even" } else { "odd" };
+                    record_launch(&stats_file, tag, Utc::now(), 5, index != 1).unwrap();
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    let stats = read_usage_stats(&stats_file).unwrap();
+    assert_eq!(stats["even"].launches, 40);
+    assert_eq!(stats["even"].runtime_ms, 200);
+    assert_eq!(stats["even"].nonzero_exits, 0);
+    assert_eq!(stats["odd"].launches, 40);
+    assert_eq!(stats["odd"].nonzero_exits, 10);
+}
+
+#[test]
+fn days_unused_count_from_the_last_launch() {
+    let sandbox = Sandbox::new("usage-stats-days");
+    let stats_file = sandbox.root().join("wexe.stats.json");
+    let now = Utc::now();
+    record_launch(&stats_file, "app", now - Duration::days(3), 0, true).unwrap();
+    let stats = read_usage_stats(&stats_file).unwrap();
+    assert_eq!(stats["app"].days_unused(now), Some(3));
+}
+
+#[test]
+fn corrupt_statistics_are_not_overwritten() {
+    let sandbox = Sandbox::new("usage-stats-corrupt");
+    let stats_file = sandbox.root().join("wexe.stats.json");
+    // As left behind by a crash halfway through writing the file
+    let corrupt = "{\n  \"app\": {\n    \"launches\": 12,\n";
+    fs::write(&stats_file, corrupt).unwrap();
+    let e = record_launch(&stats_file, "app", Utc::now(), 0, true).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    assert!(e.to_string().contains("corrupt"), "{e}");
+    assert_eq!(fs::read_to_string(&stats_file).unwrap(), corrupt);
+    assert!(read_usage_stats(&stats_file).is_err());
+}
@@ -0,0 +1,296 @@
+//! Integration tests for the wexecfg commands, run against a sandboxed repository.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use common::*;
+
+#[test]
+fn install_copies_wexe_and_wexecfg() {
+    let sandbox = Sandbox::new("install");
+    let output = sandbox.wexecfg(&["/install"]);
+    assert!(output.status.success(), "install failed: {}", stderr(&output));
+    assert!(sandbox.stub("wexecfg").exists());
+    assert!(!sandbox.stub("wexe").exists());
+
+    let output = sandbox.wexecfg(&["/install", "-wexe"]);
+    assert!(output.status.success(), "install failed: {}", stderr(&output));
+    assert!(sandbox.stub("wexe").exists());
+    assert!(stdout(&output).contains("is already up to date"));
+}
+
+#[test]
+fn wrap_creates_a_working_application() {
+    let sandbox = Sandbox::installed("wrap");
+    let echoargs = sandbox.echoargs();
+    let output = sandbox.wexecfg(&[
+        "/wrap",
+        "-x",
+        echoargs.to_str().unwrap(),
+        "-n",
+        "echo",
+        "-a",
+        "--wrapped",
+    ]);
+    assert!(output.status.success(), "wrap failed: {}", stderr(&output));
+    assert!(sandbox.config("echo").exists());
+    // Stubs have no extension outside Windows
+    assert!(sandbox.stub("echo").exists());
+    assert!(!sandbox.wexe_home().join("echo.exe").exists());
+
+    let report = sandbox.run_echo("echo", &["arg"], &[]);
+    assert_eq!(echo_args(&report), vec!["--wrapped", "arg"]);
+
+    // Wrapping again without -F leaves the configuration alone
+    let output = sandbox.wexecfg(&["/wrap", "-x", echoargs.to_str().unwrap(), "-n", "echo"]);
+    assert!(output.status.success(), "wrap failed: {}", stderr(&output));
+    assert!(sandbox.wexe_home().join("echo.toml.tmp").exists());
+    let report = sandbox.run_echo("echo", &["arg"], &[]);
+    assert_eq!(echo_args(&report), vec!["--wrapped", "arg"]);
+}
+
+#[test]
+fn list_shows_applications_and_stub_status() {
+    let sandbox = Sandbox::installed("list");
+    sandbox.add_app("echo", r#"target = "{echoargs}""#);
+    sandbox.write_config("nostub", r#"target = "{echoargs}""#);
+    let output = sandbox.wexecfg(&["/list"]);
+    assert!(output.status.success(), "list failed: {}", stderr(&output));
+    let text = stdout(&output);
+    let echo_line = text.lines().find(|line| line.contains("echo")).unwrap();
+    assert!(!echo_line.contains("Stub missing"));
+    let nostub_line = text.lines().find(|line| line.contains("nostub")).unwrap();
+    assert!(nostub_line.contains("Stub missing"));
+    // The installed executables are not applications
+    assert!(!text.lines().any(|line| line.contains("wexecfg")));
+}
+
+#[test]
+fn fix_restores_missing_stubs_and_removes_orphans() {
+    let sandbox = Sandbox::installed("fix");
+    sandbox.add_app("echo", r#"target = "{echoargs}""#);
+    sandbox.add_app("orphan", r#"target = "{echoargs}""#);
+    fs::remove_file(sandbox.stub("echo")).unwrap();
+    fs::remove_file(sandbox.config("orphan")).unwrap();
+
+    let output = sandbox.wexecfg(&["/fix", "-all"]);
+    assert!(output.status.success(), "fix failed: {}", stderr(&output));
+    assert!(sandbox.stub("echo").exists());
+    assert!(!sandbox.stub("orphan").exists());
+    // The installed executables are left alone
+    assert!(sandbox.stub("wexe").exists());
+    assert!(sandbox.stub("wexecfg").exists());
+    sandbox.run_echo("echo", &[], &[]);
+}
+
+#[test]
+fn fix_removes_stubs_of_broken_applications() {
+    let sandbox = Sandbox::installed("fix-broken");
+    sandbox.add_app("echo", r#"target = "{echoargs}""#);
+    sandbox.write_config("echo", r#"target = "relative/path""#);
+    let output = sandbox.wexecfg(&["/fix", "echo"]);
+    assert!(output.status.success(), "fix failed: {}", stderr(&output));
+    assert!(!sandbox.stub("echo").exists());
+}
+
+#[test]
+fn drop_removes_configuration_and_stub() {
+    let sandbox = Sandbox::installed("drop");
+    sandbox.add_app("echo", r#"target = "{echoargs}""#);
+    let output = sandbox.wexecfg(&["/drop", "echo"]);
+    assert!(output.status.success(), "drop failed: {}", stderr(&output));
+    assert!(!sandbox.stub("echo").exists());
+    assert!(!sandbox.config("echo").exists());
+    // The configuration is kept as a backup
+    assert!(sandbox.wexe_home().join("echo.toml.bak").exists());
+}
+
+/// Run /scan -json with the given PATH and return its report.
+fn scan_report(sandbox: &Sandbox, path: &[PathBuf]) -> serde_json::Value {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_wexecfg"));
+    command.args(["/scan", "-json"]);
+    sandbox.sandboxed(&mut command);
+    let output = command.env("PATH", std::env::join_paths(path).unwrap()).output().unwrap();
+    assert!(output.status.success(), "scan failed: {}", stderr(&output));
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn scan_only_suggests_dropping_folders_with_wrapped_executables() {
+    let sandbox = Sandbox::installed("scan");
+    sandbox.add_app("echo", r#"target = "{echoargs}""#);
+    let empty = sandbox.root().join("empty");
+    fs::create_dir_all(&empty).unwrap();
+    let report = scan_report(&sandbox, &[sandbox.wexe_home(), sandbox.root().join("tools"), empty]);
+    let folders = report["folders"].as_array().unwrap();
+    let find = |name: &str| {
+        folders
+            .iter()
+            .find(|f| f["folder"].as_str().unwrap().ends_with(name))
+            .unwrap_or_else(|| panic!("{name} not in scan report"))
+    };
+    assert_eq!(find("tools")["droppable"], true);
+    // A folder without executables has nothing wrapped and is not suggested for dropping
+    assert_eq!(find("empty")["executables"], 0);
+    assert_eq!(find("empty")["droppable"], false);
+}
+
+#[test]
+fn scan_reports_an_own_target_earlier_on_path_as_shadowing_the_stub() {
+    let sandbox = Sandbox::installed("scan-own-target");
+    sandbox.add_app("echoargs", r#"target = "{echoargs}""#);
+    let tools = sandbox.root().join("tools");
+    let shadowed_tags = |report: &serde_json::Value| -> Vec<String> {
+        report["shadowed"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|finding| finding["tag"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // After the wexe folder, the target is simply what the stub runs
+    let report = scan_report(&sandbox, &[sandbox.wexe_home(), tools.clone()]);
+    assert!(shadowed_tags(&report).is_empty(), "{report}");
+
+    // Before it, the target bypasses the stub
+    let report = scan_report(&sandbox, &[tools, sandbox.wexe_home()]);
+    assert_eq!(shadowed_tags(&report), vec!["echoargs"]);
+}
+
+#[test]
+fn retarget_rebases_os_sections_and_profiles() {
+    let sandbox = Sandbox::installed("retarget");
+    sandbox.write_config(
+        "echo",
+        "target = \"/old/root/missing\"\n\
+        [[args.paths.map]]\nfrom = \"/old/root/data/\"\nto = \"D:/\"\n\
+        [os.linux]\ntarget = \"/old/root/echoargs\"\n\
+        [profiles.dev.env.set]\nDEV_HOME = \"/old/root/dev\"\n\
+        [profiles.dev.env.pathlike.PATH]\nremove = [\"/old/root/bin\"]\n",
+    );
+    let tools = sandbox.root().join("tools");
+    let tools = tools.to_str().unwrap();
+    let output = sandbox.wexecfg(&["/retarget", "--from", "/old/root", "--to", tools]);
+    assert!(output.status.success(), "retarget failed: {}", stderr(&output));
+    let text = fs::read_to_string(sandbox.config("echo")).unwrap();
+    assert!(!text.contains("/old/root"), "not rebased: {text}");
+    assert!(text.contains(&format!("target = \"{tools}/echoargs\"")));
+    assert!(text.contains(&format!("DEV_HOME = \"{tools}/dev\"")));
+    assert!(text.contains(&format!("from = \"{tools}/data/\"")));
+    assert!(text.contains(&format!("\"{tools}/bin\"")));
+    if cfg!(target_os = "linux") {
+        // The rebased [os.linux] target is the one that is validated and launched
+        assert!(stdout(&output).contains("new target exists"));
+        let output = sandbox.wexecfg(&["/fix", "echo"]);
+        assert!(output.status.success(), "fix failed: {}", stderr(&output));
+        let report = sandbox.run_echo("echo", &["+dev"], &[]);
+        assert_eq!(echo_env(&report, "DEV_HOME"), Some(format!("{tools}/dev").as_str()));
+    }
+}
+
+#[test]
+fn rebasing_from_the_root_is_rejected() {
+    let sandbox = Sandbox::installed("rebase-root");
+    sandbox.add_app("echo", "target = \"{echoargs}\"\n[env.set]\nFOO = \"\"\n");
+    let text = fs::read_to_string(sandbox.config("echo")).unwrap();
+
+    let output = sandbox.wexecfg(&["/retarget", "--from", "/", "--to", "/new"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("would match every path"), "{}", stderr(&output));
+    assert_eq!(fs::read_to_string(sandbox.config("echo")).unwrap(), text);
+
+    let output = sandbox.wexecfg(&["/export"]);
+    assert!(output.status.success(), "export failed: {}", stderr(&output));
+    let bundle = sandbox.root().join("bundle.toml");
+    fs::write(&bundle, &output.stdout).unwrap();
+    let bundle = bundle.to_str().unwrap();
+    let output =
+        sandbox.wexecfg(&["/import", bundle, "--overwrite", "--from", "//", "--to", "/new"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("would match every path"), "{}", stderr(&output));
+    assert_eq!(fs::read_to_string(sandbox.config("echo")).unwrap(), text);
+}
+
+#[test]
+fn migrate_upgrades_older_configurations() {
+    let sandbox = Sandbox::installed("migrate");
+    sandbox.add_app("old", "# keep me\ntarget = \"{echoargs}\" # the target\n");
+    sandbox.add_app("current", "target = \"{echoargs}\"\nschema = 1\n");
+    let current_text = fs::read_to_string(sandbox.config("current")).unwrap();
+
+    let output = sandbox.wexecfg(&["/migrate", "-all", "--dry-run"]);
+    assert!(output.status.success(), "migrate failed: {}", stderr(&output));
+    assert!(!fs::read_to_string(sandbox.config("old")).unwrap().contains("schema"));
+
+    let output = sandbox.wexecfg(&["/migrate", "-all"]);
+    assert!(output.status.success(), "migrate failed: {}", stderr(&output));
+    let old_text = fs::read_to_string(sandbox.config("old")).unwrap();
+    assert!(old_text.starts_with("# keep me\n"));
+    assert!(old_text.contains("# the target\nschema = 1\n"));
+    assert_eq!(fs::read_to_string(sandbox.config("current")).unwrap(), current_text);
+    sandbox.run_echo("old", &[], &[]);
+
+    // Configurations for a newer schema are left alone
+    sandbox.write_config("newer", "target = \"{echoargs}\"\nschema = 99\n");
+    let output = sandbox.wexecfg(&["/migrate", "newer"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("uses configuration schema 99"));
+}
+
+#[test]
+fn show_displays_the_selected_profile() {
+    let sandbox = Sandbox::installed("show");
+    sandbox.add_app(
+        "echo",
+        r#"
+target = "{echoargs}"
+default_profile = "release"
+[profiles.debug.args]
+prepend = ["--debug"]
+[profiles.release.args]
+prepend = ["--release"]
+"#,
+    );
+    let output = sandbox.wexecfg(&["/show", "echo"]);
+    assert!(output.status.success(), "show failed: {}", stderr(&output));
+    let text = stdout(&output);
+    assert!(text.contains("selected by"));
+    assert!(text.contains("default_profile"));
+    assert!(text.contains("\"--release\""));
+
+    let output = sandbox.wexecfg(&["/show", "echo", "--profile", "debug"]);
+    assert!(output.status.success(), "show failed: {}", stderr(&output));
+    let text = stdout(&output);
+    assert!(text.contains("\"--debug\""));
+    assert!(!text.contains("\"--release\""));
+}
+
+#[test]
+fn show_effective_reports_the_surviving_environment() {
+    let sandbox = Sandbox::installed("show-effective");
+    sandbox.add_app(
+        "echo",
+        r#"
+target = "{echoargs}"
+[env]
+clear = true
+keep = ["HOME", "XDG_*"]
+[env.set]
+WEXE_TEST_SET = "value"
+"#,
+    );
+    let output = sandbox.wexecfg(&["/show", "echo", "--effective"]);
+    assert!(output.status.success(), "show failed: {}", stderr(&output));
+    let text = stdout(&output);
+    assert!(text.contains("Effective env"));
+    assert!(text.contains("XDG_CONFIG_HOME"));
+    assert!(text.contains("WEXE_TEST_SET"));
+    // The sandbox sets WEXE_DEBUG for wexecfg, but it is not kept
+    assert!(!text.contains("WEXE_DEBUG"));
+}